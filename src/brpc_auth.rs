@@ -2,19 +2,23 @@
 //!
 //! This module provides automatic detection of bitcoind RPC credentials
 //! through multiple methods in priority order:
-//! 1. Explicit smaug_brpc_user + smaug_brpc_pass
-//! 2. Explicit smaug_brpc_cookie_dir
-//! 3. listconfigs RPC for bitcoin-rpc* options
-//! 4. Auto-detect cookie at standard paths
-//! 5. Parse ~/.bitcoin/bitcoin.conf
-//! 6. Graceful startup with warning (returns None)
-
-use bitcoincore_rpc::Auth;
+//! 1. Explicit smaug_brpc_url / smaug_brpc_user + smaug_brpc_pass / smaug_brpc_cookie_dir options
+//! 2. SMAUG_BRPC_URL / SMAUG_BRPC_USER+PASS / SMAUG_BRPC_COOKIE_DIR environment variables
+//! 3. The same keys from a `.env` file in the plugin's working directory
+//! 4. listconfigs RPC for bitcoin-rpc* options
+//! 5. Auto-detect cookie at standard paths, honoring a `smaug_brpc_datadir`
+//!    option or `datadir=` in bitcoin.conf when bitcoind's data directory
+//!    isn't `~/.bitcoin`
+//! 6. Parse ~/.bitcoin/bitcoin.conf, following `includeconf=` directives
+//! 7. Graceful startup with warning (returns None)
+
+use bitcoincore_rpc::{Auth, Client, RpcApi};
 use home::home_dir;
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::UnixStream;
 
@@ -29,8 +33,13 @@ pub struct BrpcConfig {
 /// Result of credential detection - either configured or unconfigured with a message.
 #[derive(Debug)]
 pub enum DetectionResult {
-    /// Successfully detected credentials.
-    Configured(BrpcConfig),
+    /// Successfully detected credentials. `txindex_warning` is set when the
+    /// detected node doesn't appear to have `txindex=1` enabled -- Smaug can
+    /// still start, but historical descriptor scans will be unreliable.
+    Configured {
+        config: BrpcConfig,
+        txindex_warning: Option<String>,
+    },
     /// No credentials found - plugin should start in unconfigured mode.
     Unconfigured(String),
 }
@@ -38,101 +47,386 @@ pub enum DetectionResult {
 /// Detects bitcoind RPC configuration using multiple fallback methods.
 ///
 /// # Priority Order
-/// 1. Explicit `smaug_brpc_user` + `smaug_brpc_pass` options
-/// 2. Explicit `smaug_brpc_cookie_dir` option
-/// 3. `listconfigs` RPC for `bitcoin-rpc*` options from CLN
-/// 4. Auto-detect cookie at standard paths based on network
-/// 5. Parse `~/.bitcoin/bitcoin.conf` for rpcuser/rpcpassword
-/// 6. Return Unconfigured with helpful message
+/// 1. Explicit `smaug_brpc_url` / `smaug_brpc_user`+`smaug_brpc_pass` / `smaug_brpc_cookie_dir` options
+/// 2. `SMAUG_BRPC_URL` / `SMAUG_BRPC_USER`+`SMAUG_BRPC_PASS` / `SMAUG_BRPC_COOKIE_DIR` environment variables
+/// 3. The same keys read from a `.env` file in the plugin's working directory
+/// 4. `listconfigs` RPC for `bitcoin-rpc*` options from CLN
+/// 5. Auto-detect cookie at standard paths based on network
+/// 6. Parse `~/.bitcoin/bitcoin.conf` for rpcuser/rpcpassword
+/// 7. Return Unconfigured with helpful message
+///
+/// Once a config is resolved via any of the above, also checks whether the
+/// node has `txindex` enabled (required for Smaug to reliably scan arbitrary
+/// descriptors for historical activity) and attaches a prominent warning to
+/// the result if it doesn't.
 pub async fn detect_brpc_config(
     brpc_host: &str,
     brpc_port_opt: Option<i64>,
+    brpc_url_opt: Option<String>,
     brpc_user_opt: Option<String>,
     brpc_pass_opt: Option<String>,
     brpc_cookie_dir_opt: Option<String>,
+    brpc_datadir_opt: Option<String>,
     network: &str,
     rpc_file: &Path,
 ) -> Result<DetectionResult, anyhow::Error> {
-    // Priority 1: Explicit smaug_brpc_user + smaug_brpc_pass
-    if let Some(user) = brpc_user_opt {
-        if let Some(pass) = brpc_pass_opt.clone() {
-            let port = resolve_port(brpc_port_opt, network);
-            log::debug!("Using explicit smaug_brpc_user/pass credentials");
-            return Ok(DetectionResult::Configured(BrpcConfig {
-                host: brpc_host.to_string(),
-                port,
-                auth: Auth::UserPass(user, pass),
-            }));
-        } else {
-            return Err(anyhow::anyhow!(
-                "specified `smaug_brpc_user` but did not specify `smaug_brpc_pass`"
+    let config = resolve_brpc_config(
+        brpc_host,
+        brpc_port_opt,
+        brpc_url_opt,
+        brpc_user_opt,
+        brpc_pass_opt,
+        brpc_cookie_dir_opt,
+        brpc_datadir_opt,
+        network,
+        rpc_file,
+    )
+    .await?;
+
+    let config = match config {
+        Some(config) => config,
+        None => {
+            let help_message = format!(
+                "No bitcoind RPC credentials found. Smaug will start but cannot function until configured.\n\
+                \n\
+                To configure bitcoind access, use one of these methods:\n\
+                \n\
+                1. Set explicit credentials in CLN config:\n\
+                   smaug_brpc_user=<rpcuser>\n\
+                   smaug_brpc_pass=<rpcpassword>\n\
+                   smaug_brpc_port=<port>  # optional, defaults based on network\n\
+                \n\
+                2. Point to cookie file directory:\n\
+                   smaug_brpc_cookie_dir=/path/to/bitcoin/datadir\n\
+                \n\
+                3. Ensure CLN has bitcoin-rpcuser/bitcoin-rpcpassword set\n\
+                \n\
+                4. Use standard cookie file location (~/.bitcoin/[network]/.cookie)\n\
+                \n\
+                5. Add rpcuser/rpcpassword to ~/.bitcoin/bitcoin.conf"
+            );
+            log::warn!("{}", help_message);
+            return Ok(DetectionResult::Unconfigured(help_message));
+        }
+    };
+
+    let txindex_warning = check_txindex_warning(&config);
+    Ok(DetectionResult::Configured {
+        config,
+        txindex_warning,
+    })
+}
+
+/// Runs the actual priority chain, returning `None` if no tier yielded a
+/// config rather than the `Unconfigured` help text -- that's
+/// [`detect_brpc_config`]'s job once it also knows the txindex status.
+async fn resolve_brpc_config(
+    brpc_host: &str,
+    brpc_port_opt: Option<i64>,
+    brpc_url_opt: Option<String>,
+    brpc_user_opt: Option<String>,
+    brpc_pass_opt: Option<String>,
+    brpc_cookie_dir_opt: Option<String>,
+    brpc_datadir_opt: Option<String>,
+    network: &str,
+    rpc_file: &Path,
+) -> Result<Option<BrpcConfig>, anyhow::Error> {
+    let mut brpc_host = brpc_host.to_string();
+    let mut brpc_port_opt = brpc_port_opt;
+
+    // Priority 1: explicit smaug_brpc_* options
+    if let Some(config) = try_explicit_source(
+        &mut brpc_host,
+        &mut brpc_port_opt,
+        network,
+        brpc_url_opt,
+        brpc_user_opt,
+        brpc_pass_opt,
+        brpc_cookie_dir_opt,
+        "explicit smaug_brpc_* options",
+    )? {
+        return Ok(Some(config));
+    }
+
+    // Priority 2: SMAUG_BRPC_* environment variables
+    if let Some(config) = try_explicit_source(
+        &mut brpc_host,
+        &mut brpc_port_opt,
+        network,
+        std::env::var("SMAUG_BRPC_URL").ok(),
+        std::env::var("SMAUG_BRPC_USER").ok(),
+        std::env::var("SMAUG_BRPC_PASS").ok(),
+        std::env::var("SMAUG_BRPC_COOKIE_DIR").ok(),
+        "SMAUG_BRPC_* environment variables",
+    )? {
+        return Ok(Some(config));
+    }
+
+    // Priority 3: the same keys read from a `.env` file in the working directory
+    let dotenv = read_dotenv_file(Path::new(".env"));
+    if let Some(config) = try_explicit_source(
+        &mut brpc_host,
+        &mut brpc_port_opt,
+        network,
+        dotenv.get("SMAUG_BRPC_URL").cloned(),
+        dotenv.get("SMAUG_BRPC_USER").cloned(),
+        dotenv.get("SMAUG_BRPC_PASS").cloned(),
+        dotenv.get("SMAUG_BRPC_COOKIE_DIR").cloned(),
+        "`.env` file",
+    )? {
+        return Ok(Some(config));
+    }
+    let brpc_host = brpc_host.as_str();
+
+    // Priority 4: listconfigs RPC for bitcoin-rpc* options
+    if let Some(config) = try_listconfigs(brpc_host, brpc_port_opt, network, rpc_file).await? {
+        log::debug!("Using credentials from CLN listconfigs (bitcoin-rpc* options)");
+        return Ok(Some(config));
+    }
+
+    // Priority 5: Auto-detect cookie at standard paths
+    if let Some(config) =
+        try_standard_cookie_path(brpc_host, brpc_port_opt, brpc_datadir_opt.as_deref(), network)?
+    {
+        log::debug!("Using cookie file at standard path");
+        return Ok(Some(config));
+    }
+
+    // Priority 6: Parse ~/.bitcoin/bitcoin.conf
+    if let Some(config) = try_bitcoin_conf(brpc_host, brpc_port_opt, network)? {
+        log::debug!("Using credentials from ~/.bitcoin/bitcoin.conf");
+        return Ok(Some(config));
+    }
+
+    Ok(None)
+}
+
+/// Checks whether `config`'s node has `txindex` enabled, returning a
+/// human-readable warning message if it doesn't (or if the check itself
+/// couldn't be completed). Returns `None` when txindex is confirmed on.
+fn check_txindex_warning(config: &BrpcConfig) -> Option<String> {
+    let client = match build_rpc_client(&config.host, config.port, &config.auth) {
+        Ok(c) => c,
+        Err(e) => {
+            return Some(format!(
+                "Could not confirm bitcoind has `txindex=1` enabled (failed to connect: {e}). \
+                Descriptor scanning requires `txindex=1` in bitcoin.conf; without it, historical \
+                transactions for newly-watched descriptors may be missed."
             ));
         }
+    };
+
+    match runtime_txindex_enabled(&client) {
+        Ok(true) => None,
+        Ok(false) => Some(
+            "bitcoind does not appear to have `txindex` enabled. Smaug relies on \
+            `txindex=1` to scan arbitrary descriptors for historical activity; without it, \
+            transactions older than your node's pruning/retention window will be missed. \
+            Add `txindex=1` to bitcoin.conf and restart bitcoind with `-reindex` to fix this."
+                .to_string(),
+        ),
+        Err(e) => Some(format!(
+            "Could not confirm bitcoind has `txindex=1` enabled ({e}). Descriptor scanning \
+            requires `txindex=1` in bitcoin.conf; without it, historical transactions for \
+            newly-watched descriptors may be missed."
+        )),
     }
+}
 
-    // Priority 2: Explicit smaug_brpc_cookie_dir
-    if let Some(cookie_dir) = brpc_cookie_dir_opt {
-        let cookie_path = PathBuf::from(&cookie_dir).join(".cookie");
-        if cookie_path.exists() {
-            let port = resolve_port(brpc_port_opt, network);
+/// Calls `getindexinfo` to check for an enabled `txindex`, which only lists
+/// indexes that are actually built and caught up. Falls back to
+/// `getblockchaininfo`'s `pruned` flag (a weaker, indirect signal) if
+/// `getindexinfo` itself isn't available, e.g. on bitcoind older than 0.21.
+fn runtime_txindex_enabled(client: &Client) -> Result<bool, anyhow::Error> {
+    match client.call::<Value>("getindexinfo", &[]) {
+        Ok(v) => Ok(v.get("txindex").is_some()),
+        Err(e) => {
             log::debug!(
-                "Using explicit cookie file from smaug_brpc_cookie_dir: {}",
-                cookie_path.display()
+                "getindexinfo unavailable ({}), falling back to getblockchaininfo",
+                e
             );
-            return Ok(DetectionResult::Configured(BrpcConfig {
-                host: brpc_host.to_string(),
-                port,
-                auth: Auth::CookieFile(cookie_path),
+            let info: Value = client.call("getblockchaininfo", &[])?;
+            let pruned = info
+                .get("pruned")
+                .and_then(|p| p.as_bool())
+                .unwrap_or(false);
+            Ok(!pruned)
+        }
+    }
+}
+
+/// Builds a fresh `bitcoincore_rpc::Client` for `host:port` using `auth`.
+///
+/// For `Auth::CookieFile`, the cookie path is all that's stored here -- the
+/// underlying crate reads the file's contents at this call, not before, so
+/// calling this again after bitcoind regenerates `.cookie` on restart picks
+/// up the new credentials instead of reusing a stale materialized user/pass.
+pub fn build_rpc_client(host: &str, port: u16, auth: &Auth) -> Result<Client, bitcoincore_rpc::Error> {
+    Client::new_with_timeout(
+        &format!("http://{host}:{port}"),
+        auth.clone(),
+        Duration::from_secs(3600),
+    )
+}
+
+/// Whether `e` looks like a bitcoind RPC authentication failure (as opposed
+/// to, say, a connection error or a bad request), i.e. the kind of error a
+/// post-restart cookie rotation would produce.
+pub fn is_auth_error(e: &bitcoincore_rpc::Error) -> bool {
+    let msg = e.to_string().to_lowercase();
+    msg.contains("401") || msg.contains("unauthorized")
+}
+
+/// Tries to resolve full RPC credentials from a single candidate source --
+/// explicit CLN options, the process environment, or a `.env` file -- each of
+/// which carries the same three alternatives: a combined URL, a user/pass
+/// pair, or a cookie directory.
+///
+/// A bare `host:port` `url` (no `user:pass@`) overrides `host`/`port_opt` for
+/// this and every lower-priority tier even when this tier doesn't yield full
+/// credentials on its own, since it still narrows where those tiers look.
+fn try_explicit_source(
+    host: &mut String,
+    port_opt: &mut Option<i64>,
+    network: &str,
+    url: Option<String>,
+    user: Option<String>,
+    pass: Option<String>,
+    cookie_dir: Option<String>,
+    source_label: &str,
+) -> Result<Option<BrpcConfig>, anyhow::Error> {
+    if let Some(url) = url {
+        let (parsed_host, parsed_port, auth_opt) = parse_brpc_url(&url)?;
+        *host = parsed_host;
+        *port_opt = Some(parsed_port as i64);
+        if let Some(auth) = auth_opt {
+            log::debug!("Using {source_label} (combined url)");
+            return Ok(Some(BrpcConfig {
+                host: host.clone(),
+                port: parsed_port,
+                auth,
             }));
-        } else {
+        }
+    }
+
+    if let Some(user) = user {
+        let pass = pass.ok_or_else(|| {
+            anyhow::anyhow!("specified a brpc user but no password via {source_label}")
+        })?;
+        let port = resolve_port(*port_opt, network);
+        log::debug!("Using {source_label} (user/pass)");
+        return Ok(Some(BrpcConfig {
+            host: host.clone(),
+            port,
+            auth: Auth::UserPass(user, pass),
+        }));
+    }
+
+    if let Some(cookie_dir) = cookie_dir {
+        let cookie_path = PathBuf::from(&cookie_dir).join(".cookie");
+        if !cookie_path.exists() {
             return Err(anyhow::anyhow!(
-                "Nonexistent cookie file specified in smaug_brpc_cookie_dir: {}",
+                "nonexistent cookie file specified via {source_label}: {}",
                 cookie_path.display()
             ));
         }
+        let port = resolve_port(*port_opt, network);
+        log::debug!("Using {source_label} (cookie dir)");
+        return Ok(Some(BrpcConfig {
+            host: host.clone(),
+            port,
+            auth: Auth::CookieFile(cookie_path),
+        }));
     }
 
-    // Priority 3: listconfigs RPC for bitcoin-rpc* options
-    if let Some(config) = try_listconfigs(brpc_host, brpc_port_opt, network, rpc_file).await? {
-        log::debug!("Using credentials from CLN listconfigs (bitcoin-rpc* options)");
-        return Ok(DetectionResult::Configured(config));
+    Ok(None)
+}
+
+/// Parses a simple `.env`-style file (`KEY=VALUE` lines, `#` comments, blank
+/// lines ignored, surrounding quotes stripped) into a key/value map. Returns
+/// an empty map if the file doesn't exist or can't be read -- a missing
+/// `.env` is the common case, not an error.
+fn read_dotenv_file(path: &Path) -> HashMap<String, String> {
+    let mut values = HashMap::new();
+    let content = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(_) => return values,
+    };
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            let key = key.trim().to_string();
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                .unwrap_or(value)
+                .to_string();
+            values.insert(key, value);
+        }
     }
+    values
+}
 
-    // Priority 4: Auto-detect cookie at standard paths
-    if let Some(config) = try_standard_cookie_path(brpc_host, brpc_port_opt, network)? {
-        log::debug!("Using cookie file at standard path");
-        return Ok(DetectionResult::Configured(config));
+/// Parses a combined `smaug_brpc_url` connection string of the form
+/// `[user:pass@]host:port` into its host, port, and optional auth.
+///
+/// The auth half (if present) is split from the host half on the *last* `@`,
+/// so passwords containing `@` are not mistaken for a second separator. The
+/// host half is then split on its *last* `:` to separate host from port.
+/// Returns `auth = None` when no `user:pass@` prefix is given, so the caller
+/// can fall back to the existing cookie/listconfigs detection chain for auth
+/// while still using the parsed host/port.
+fn parse_brpc_url(url: &str) -> Result<(String, u16, Option<Auth>), anyhow::Error> {
+    let (auth_part, host_part) = match url.rsplit_once('@') {
+        Some((auth, host)) => (Some(auth), host),
+        None => (None, url),
+    };
+
+    let (host, port_str) = host_part.rsplit_once(':').ok_or_else(|| {
+        anyhow::anyhow!(
+            "invalid smaug_brpc_url '{}': expected '[user:pass@]host:port'",
+            url
+        )
+    })?;
+    if host.is_empty() {
+        return Err(anyhow::anyhow!(
+            "invalid smaug_brpc_url '{}': host is empty",
+            url
+        ));
     }
+    let port: u16 = port_str.parse().map_err(|_| {
+        anyhow::anyhow!(
+            "invalid smaug_brpc_url '{}': port must be a number, got '{}'",
+            url,
+            port_str
+        )
+    })?;
 
-    // Priority 5: Parse ~/.bitcoin/bitcoin.conf
-    if let Some(config) = try_bitcoin_conf(brpc_host, brpc_port_opt, network)? {
-        log::debug!("Using credentials from ~/.bitcoin/bitcoin.conf");
-        return Ok(DetectionResult::Configured(config));
-    }
-
-    // Priority 6: Graceful startup with warning
-    let help_message = format!(
-        "No bitcoind RPC credentials found. Smaug will start but cannot function until configured.\n\
-        \n\
-        To configure bitcoind access, use one of these methods:\n\
-        \n\
-        1. Set explicit credentials in CLN config:\n\
-           smaug_brpc_user=<rpcuser>\n\
-           smaug_brpc_pass=<rpcpassword>\n\
-           smaug_brpc_port=<port>  # optional, defaults based on network\n\
-        \n\
-        2. Point to cookie file directory:\n\
-           smaug_brpc_cookie_dir=/path/to/bitcoin/datadir\n\
-        \n\
-        3. Ensure CLN has bitcoin-rpcuser/bitcoin-rpcpassword set\n\
-        \n\
-        4. Use standard cookie file location (~/.bitcoin/[network]/.cookie)\n\
-        \n\
-        5. Add rpcuser/rpcpassword to ~/.bitcoin/bitcoin.conf"
-    );
+    let auth = match auth_part {
+        Some(auth) => {
+            let (user, pass) = auth.split_once(':').ok_or_else(|| {
+                anyhow::anyhow!(
+                    "invalid smaug_brpc_url '{}': expected 'user:pass' before '@'",
+                    url
+                )
+            })?;
+            if pass.contains(':') {
+                return Err(anyhow::anyhow!(
+                    "invalid smaug_brpc_url '{}': more than one ':' in the 'user:pass' half",
+                    url
+                ));
+            }
+            Some(Auth::UserPass(user.to_owned(), pass.to_owned()))
+        }
+        None => None,
+    };
 
-    log::warn!("{}", help_message);
-    Ok(DetectionResult::Unconfigured(help_message))
+    Ok((host.to_owned(), port, auth))
 }
 
 /// Resolves the RPC port based on explicit option or network defaults.
@@ -274,27 +568,51 @@ fn parse_listconfigs_response(
     Ok(None)
 }
 
-/// Tries to find cookie file at standard Bitcoin Core paths.
+/// Reads a global `datadir=` entry from `~/.bitcoin/bitcoin.conf`, if any.
 ///
-/// Network to path mapping:
-/// - bitcoin (mainnet): ~/.bitcoin/.cookie
-/// - testnet: ~/.bitcoin/testnet3/.cookie
-/// - regtest: ~/.bitcoin/regtest/.cookie
-/// - signet: ~/.bitcoin/signet/.cookie
+/// `-conf`'s default location doesn't move when `datadir` is set, so this is
+/// the one fixed place to look for it regardless of where the data actually
+/// lives. The same value is used for every network.
+fn configured_datadir() -> Option<PathBuf> {
+    let home = home_dir()?;
+    let conf_path = home.join(".bitcoin").join("bitcoin.conf");
+    let content = fs::read_to_string(&conf_path).ok()?;
+    let base_dir = conf_path.parent().unwrap_or(&home);
+    // Parsed as for mainnet: a global `datadir` (or one from an
+    // `includeconf` file) is read, and a `[main]` section entry overrides it.
+    parse_bitcoin_conf(&content, "bitcoin", base_dir)
+        .get("datadir")
+        .map(PathBuf::from)
+}
+
+/// Tries to find cookie file at standard Bitcoin Core paths, rooted at
+/// `datadir_opt` when given (falling back to `datadir=` parsed from
+/// `~/.bitcoin/bitcoin.conf`, then to `~/.bitcoin` itself) -- bitcoind's
+/// `.cookie` lives under the configured data directory, not always
+/// `~/.bitcoin`, e.g. when it's mounted on a separate volume.
+///
+/// Network to path mapping, relative to the data directory:
+/// - bitcoin (mainnet): <datadir>/.cookie
+/// - testnet: <datadir>/testnet3/.cookie
+/// - regtest: <datadir>/regtest/.cookie
+/// - signet: <datadir>/signet/.cookie
 fn try_standard_cookie_path(
     brpc_host: &str,
     brpc_port_opt: Option<i64>,
+    datadir_opt: Option<&str>,
     network: &str,
 ) -> Result<Option<BrpcConfig>, anyhow::Error> {
-    let home = match home_dir() {
-        Some(h) => h,
-        None => {
-            log::debug!("Cannot determine home directory for cookie auto-detection");
-            return Ok(None);
-        }
+    let bitcoin_dir = match datadir_opt.map(PathBuf::from).or_else(configured_datadir) {
+        Some(dir) => dir,
+        None => match home_dir() {
+            Some(h) => h.join(".bitcoin"),
+            None => {
+                log::debug!("Cannot determine home directory for cookie auto-detection");
+                return Ok(None);
+            }
+        },
     };
 
-    let bitcoin_dir = home.join(".bitcoin");
     let cookie_path = match network {
         "bitcoin" => bitcoin_dir.join(".cookie"),
         "testnet" => bitcoin_dir.join("testnet3").join(".cookie"),
@@ -354,7 +672,8 @@ fn try_bitcoin_conf(
         }
     };
 
-    let parsed = parse_bitcoin_conf(&content, network);
+    let base_dir = conf_path.parent().unwrap_or(&home).to_path_buf();
+    let parsed = parse_bitcoin_conf(&content, network, &base_dir);
 
     if let (Some(user), Some(pass)) = (parsed.get("rpcuser"), parsed.get("rpcpassword")) {
         let host = parsed
@@ -378,13 +697,32 @@ fn try_bitcoin_conf(
     Ok(None)
 }
 
-/// Parses bitcoin.conf content, handling network-specific sections.
+/// How many `includeconf=` hops to follow before giving up, matching Core's
+/// own defence against unbounded/cyclical includes.
+const MAX_INCLUDECONF_DEPTH: u32 = 10;
+
+/// Parses bitcoin.conf content, handling network-specific sections and
+/// `includeconf=` directives.
 ///
-/// Section names: [main] (mainnet), [test] (testnet), [regtest], [signet]
-fn parse_bitcoin_conf(content: &str, network: &str) -> HashMap<String, String> {
+/// Section names: [main] (mainnet), [test] (testnet), [regtest], [signet].
+/// `base_dir` is the directory `includeconf=` paths are resolved relative
+/// to -- normally the directory containing this `bitcoin.conf`.
+fn parse_bitcoin_conf(content: &str, network: &str, base_dir: &Path) -> HashMap<String, String> {
+    let mut visited = HashSet::new();
+    parse_bitcoin_conf_recursive(content, network, base_dir, &mut visited, 0)
+}
+
+fn parse_bitcoin_conf_recursive(
+    content: &str,
+    network: &str,
+    base_dir: &Path,
+    visited: &mut HashSet<PathBuf>,
+    depth: u32,
+) -> HashMap<String, String> {
     let mut global_values: HashMap<String, String> = HashMap::new();
     let mut section_values: HashMap<String, String> = HashMap::new();
     let mut current_section: Option<String> = None;
+    let mut includes: Vec<String> = Vec::new();
 
     // Map CLN network names to bitcoin.conf section names
     let target_section = match network {
@@ -414,6 +752,11 @@ fn parse_bitcoin_conf(content: &str, network: &str) -> HashMap<String, String> {
             let key = key.trim().to_string();
             let value = value.trim().to_string();
 
+            if key == "includeconf" {
+                includes.push(value);
+                continue;
+            }
+
             match &current_section {
                 None => {
                     // Global section - applies to all networks
@@ -435,6 +778,56 @@ fn parse_bitcoin_conf(content: &str, network: &str) -> HashMap<String, String> {
         global_values.insert(key, value);
     }
 
+    if depth >= MAX_INCLUDECONF_DEPTH {
+        if !includes.is_empty() {
+            log::warn!(
+                "bitcoin.conf includeconf nesting too deep (>{MAX_INCLUDECONF_DEPTH}), ignoring further includes"
+            );
+        }
+        return global_values;
+    }
+
+    for include in includes {
+        let include_path = base_dir.join(&include);
+        let canonical = fs::canonicalize(&include_path).unwrap_or_else(|_| include_path.clone());
+        if !visited.insert(canonical) {
+            log::warn!(
+                "ignoring includeconf cycle at {}",
+                include_path.display()
+            );
+            continue;
+        }
+
+        let include_content = match fs::read_to_string(&include_path) {
+            Ok(c) => c,
+            Err(e) => {
+                log::debug!(
+                    "could not read includeconf file {}: {}",
+                    include_path.display(),
+                    e
+                );
+                continue;
+            }
+        };
+        let include_base_dir = include_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| base_dir.to_path_buf());
+
+        let included = parse_bitcoin_conf_recursive(
+            &include_content,
+            network,
+            &include_base_dir,
+            visited,
+            depth + 1,
+        );
+        // Included values fill in keys not already set by the parent file,
+        // matching Core's "first definition wins" precedence.
+        for (key, value) in included {
+            global_values.entry(key).or_insert(value);
+        }
+    }
+
     global_values
 }
 
@@ -442,6 +835,75 @@ fn parse_bitcoin_conf(content: &str, network: &str) -> HashMap<String, String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_brpc_url_with_auth() {
+        let (host, port, auth) = parse_brpc_url("alice:secret@127.0.0.1:8332").unwrap();
+        assert_eq!(host, "127.0.0.1");
+        assert_eq!(port, 8332);
+        match auth {
+            Some(Auth::UserPass(u, p)) => {
+                assert_eq!(u, "alice");
+                assert_eq!(p, "secret");
+            }
+            other => panic!("expected Auth::UserPass, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_brpc_url_host_port_only() {
+        let (host, port, auth) = parse_brpc_url("127.0.0.1:8332").unwrap();
+        assert_eq!(host, "127.0.0.1");
+        assert_eq!(port, 8332);
+        assert!(auth.is_none());
+    }
+
+    #[test]
+    fn test_parse_brpc_url_missing_port() {
+        assert!(parse_brpc_url("alice:secret@127.0.0.1").is_err());
+        assert!(parse_brpc_url("127.0.0.1").is_err());
+    }
+
+    #[test]
+    fn test_parse_brpc_url_non_numeric_port() {
+        assert!(parse_brpc_url("127.0.0.1:notaport").is_err());
+    }
+
+    #[test]
+    fn test_parse_brpc_url_extra_colon_in_auth() {
+        assert!(parse_brpc_url("alice:se:cret@127.0.0.1:8332").is_err());
+    }
+
+    #[test]
+    fn test_read_dotenv_file() {
+        let dir = std::env::temp_dir().join(format!(
+            "smaug_test_dotenv_{:?}",
+            std::thread::current().id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(".env");
+        fs::write(
+            &path,
+            "# a comment\n\nSMAUG_BRPC_USER=alice\nSMAUG_BRPC_PASS=\"secret\"\nSMAUG_BRPC_COOKIE_DIR='/data/.cookie-dir'\n",
+        )
+        .unwrap();
+
+        let values = read_dotenv_file(&path);
+        assert_eq!(values.get("SMAUG_BRPC_USER"), Some(&"alice".to_string()));
+        assert_eq!(values.get("SMAUG_BRPC_PASS"), Some(&"secret".to_string()));
+        assert_eq!(
+            values.get("SMAUG_BRPC_COOKIE_DIR"),
+            Some(&"/data/.cookie-dir".to_string())
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_read_dotenv_file_missing_returns_empty() {
+        let values = read_dotenv_file(Path::new("/nonexistent/path/.env"));
+        assert!(values.is_empty());
+    }
+
     #[test]
     fn test_resolve_port_explicit() {
         assert_eq!(resolve_port(Some(12345), "bitcoin"), 12345);
@@ -464,7 +926,7 @@ rpcuser=alice
 rpcpassword=secret123
 rpcport=8332
 "#;
-        let parsed = parse_bitcoin_conf(content, "bitcoin");
+        let parsed = parse_bitcoin_conf(content, "bitcoin", Path::new("."));
         assert_eq!(parsed.get("rpcuser"), Some(&"alice".to_string()));
         assert_eq!(parsed.get("rpcpassword"), Some(&"secret123".to_string()));
         assert_eq!(parsed.get("rpcport"), Some(&"8332".to_string()));
@@ -489,22 +951,44 @@ rpcport=18332
 rpcuser=regtest_user
 "#;
         // Test mainnet
-        let parsed = parse_bitcoin_conf(content, "bitcoin");
+        let parsed = parse_bitcoin_conf(content, "bitcoin", Path::new("."));
         assert_eq!(parsed.get("rpcuser"), Some(&"mainnet_user".to_string()));
         assert_eq!(parsed.get("rpcpassword"), Some(&"mainnet_pass".to_string()));
 
         // Test testnet
-        let parsed = parse_bitcoin_conf(content, "testnet");
+        let parsed = parse_bitcoin_conf(content, "testnet", Path::new("."));
         assert_eq!(parsed.get("rpcuser"), Some(&"testnet_user".to_string()));
         assert_eq!(parsed.get("rpcpassword"), Some(&"testnet_pass".to_string()));
         assert_eq!(parsed.get("rpcport"), Some(&"18332".to_string()));
 
         // Test regtest - section only overrides rpcuser, rpcpassword comes from global
-        let parsed = parse_bitcoin_conf(content, "regtest");
+        let parsed = parse_bitcoin_conf(content, "regtest", Path::new("."));
         assert_eq!(parsed.get("rpcuser"), Some(&"regtest_user".to_string()));
         assert_eq!(parsed.get("rpcpassword"), Some(&"global_pass".to_string()));
     }
 
+    #[test]
+    fn test_parse_bitcoin_conf_boolean_options() {
+        // bitcoin.conf has no real boolean type -- `txindex=1` / `txindex=0`
+        // are just strings like everything else, and parse_bitcoin_conf
+        // should hand them back verbatim for the caller to interpret.
+        let content = r#"
+txindex=1
+
+[test]
+txindex=0
+"#;
+        let parsed = parse_bitcoin_conf(content, "bitcoin", Path::new("."));
+        assert_eq!(parsed.get("txindex"), Some(&"1".to_string()));
+
+        let parsed = parse_bitcoin_conf(content, "testnet", Path::new("."));
+        assert_eq!(parsed.get("txindex"), Some(&"0".to_string()));
+
+        // missing entirely -- caller must treat absence as "off"
+        let parsed = parse_bitcoin_conf("rpcuser=alice\n", "bitcoin", Path::new("."));
+        assert_eq!(parsed.get("txindex"), None);
+    }
+
     #[test]
     fn test_parse_bitcoin_conf_comments_and_whitespace() {
         let content = r#"
@@ -516,7 +1000,7 @@ rpcpassword=pass  # inline comment not supported, this is the password
 [main]
   rpcport = 8332
 "#;
-        let parsed = parse_bitcoin_conf(content, "bitcoin");
+        let parsed = parse_bitcoin_conf(content, "bitcoin", Path::new("."));
         assert_eq!(parsed.get("rpcuser"), Some(&"spaced_user".to_string()));
         // Note: inline comments aren't supported by bitcoin.conf, but we trim
         assert_eq!(
@@ -525,4 +1009,69 @@ rpcpassword=pass  # inline comment not supported, this is the password
         );
         assert_eq!(parsed.get("rpcport"), Some(&"8332".to_string()));
     }
+
+    #[test]
+    fn test_parse_bitcoin_conf_includeconf() {
+        let dir = std::env::temp_dir().join(format!(
+            "smaug_test_includeconf_{:?}",
+            std::thread::current().id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+
+        fs::write(dir.join("secrets.conf"), "rpcpassword=from_include\n").unwrap();
+        fs::write(
+            dir.join("bitcoin.conf"),
+            "rpcuser=alice\nincludeconf=secrets.conf\n",
+        )
+        .unwrap();
+
+        let content = fs::read_to_string(dir.join("bitcoin.conf")).unwrap();
+        let parsed = parse_bitcoin_conf(&content, "bitcoin", &dir);
+        assert_eq!(parsed.get("rpcuser"), Some(&"alice".to_string()));
+        assert_eq!(parsed.get("rpcpassword"), Some(&"from_include".to_string()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_bitcoin_conf_includeconf_parent_value_wins() {
+        let dir = std::env::temp_dir().join(format!(
+            "smaug_test_includeconf_precedence_{:?}",
+            std::thread::current().id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+
+        fs::write(dir.join("secrets.conf"), "rpcuser=included_user\n").unwrap();
+        fs::write(
+            dir.join("bitcoin.conf"),
+            "rpcuser=main_user\nincludeconf=secrets.conf\n",
+        )
+        .unwrap();
+
+        let content = fs::read_to_string(dir.join("bitcoin.conf")).unwrap();
+        let parsed = parse_bitcoin_conf(&content, "bitcoin", &dir);
+        // The including file's own value takes precedence over the included one.
+        assert_eq!(parsed.get("rpcuser"), Some(&"main_user".to_string()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_bitcoin_conf_includeconf_cycle_does_not_hang() {
+        let dir = std::env::temp_dir().join(format!(
+            "smaug_test_includeconf_cycle_{:?}",
+            std::thread::current().id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+
+        fs::write(dir.join("a.conf"), "includeconf=b.conf\nrpcuser=from_a\n").unwrap();
+        fs::write(dir.join("b.conf"), "includeconf=a.conf\nrpcpassword=from_b\n").unwrap();
+
+        let content = fs::read_to_string(dir.join("a.conf")).unwrap();
+        let parsed = parse_bitcoin_conf(&content, "bitcoin", &dir);
+        assert_eq!(parsed.get("rpcuser"), Some(&"from_a".to_string()));
+        assert_eq!(parsed.get("rpcpassword"), Some(&"from_b".to_string()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,136 @@
+//! Fiat valuation of on-chain amounts via a pluggable price oracle.
+//!
+//! Prices are fetched once per block height and cached, so every
+//! notification for the same block reuses the same quote. When the oracle is
+//! unconfigured, or unreachable with no rate cached yet, callers get `None`
+//! back instead of an error so the notification is still sent, just without
+//! the fiat fields. A transient oracle outage after at least one successful
+//! fetch instead degrades to the last known-good rate (tagged with the
+//! height it was actually quoted at), rather than dropping fiat fields the
+//! moment the oracle hiccups.
+
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::fmt;
+
+const SATS_PER_BTC: u64 = 100_000_000;
+
+#[derive(Debug)]
+pub enum FiatError {
+    Overflow,
+    OracleUnreachable(String),
+}
+
+impl fmt::Display for FiatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FiatError::Overflow => write!(f, "overflow converting sats to fiat"),
+            FiatError::OracleUnreachable(msg) => write!(f, "price oracle unreachable: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for FiatError {}
+
+/// A fiat-per-BTC price quote, valid for a specific block height.
+#[derive(Debug, Clone)]
+pub struct Rate {
+    pub currency: String,
+    pub price_per_btc: Decimal,
+    pub block_height: u32,
+}
+
+/// Converts a sat amount to fiat using `rate`, rounded to the currency's
+/// minor unit (2 decimal places, as with most fiat currencies).
+pub fn sats_to_fiat(sats: u64, rate: &Rate) -> Result<Decimal, FiatError> {
+    let btc = Decimal::from(sats)
+        .checked_div(Decimal::from(SATS_PER_BTC))
+        .ok_or(FiatError::Overflow)?;
+    let fiat = btc
+        .checked_mul(rate.price_per_btc)
+        .ok_or(FiatError::Overflow)?;
+    Ok(fiat.round_dp(2))
+}
+
+/// Fetches and caches fiat price quotes, one per block height, from a
+/// configurable oracle URL.
+#[derive(Debug, Clone, Default)]
+pub struct PriceOracle {
+    pub oracle_url: Option<String>,
+    pub currency: Option<String>,
+    cache: BTreeMap<u32, Rate>,
+}
+
+impl PriceOracle {
+    pub fn new(oracle_url: Option<String>, currency: Option<String>) -> Self {
+        Self {
+            oracle_url,
+            currency,
+            cache: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the rate for `block_height`, fetching and caching it from the
+    /// oracle if we don't already have one. Never errors: an unconfigured
+    /// oracle, or one that's unreachable with nothing cached yet, yields
+    /// `None`. A fetch failure with at least one prior successful fetch
+    /// cached instead falls back to the most recent known-good rate (itself
+    /// still tagged with the height it was quoted at, so callers never see a
+    /// rate claim a height it wasn't actually fetched for), so a transient
+    /// outage degrades gracefully instead of dropping fiat fields outright.
+    pub fn rate_at(&mut self, block_height: u32) -> Option<Rate> {
+        if let Some(rate) = self.cache.get(&block_height) {
+            return Some(rate.clone());
+        }
+        let (url, currency) = match (self.oracle_url.clone(), self.currency.clone()) {
+            (Some(u), Some(c)) => (u, c),
+            _ => return None,
+        };
+        match Self::fetch_price_per_btc(&url, block_height) {
+            Ok(price_per_btc) => {
+                let rate = Rate {
+                    currency,
+                    price_per_btc,
+                    block_height,
+                };
+                self.cache.insert(block_height, rate.clone());
+                Some(rate)
+            }
+            Err(e) => match self.cache.values().next_back() {
+                Some(last_good) => {
+                    log::warn!(
+                        "{}; falling back to last known rate (from block {}) for block {}",
+                        e,
+                        last_good.block_height,
+                        block_height
+                    );
+                    Some(last_good.clone())
+                }
+                None => {
+                    log::warn!(
+                        "{}; no cached rate available yet, omitting fiat fields for block {}",
+                        e,
+                        block_height
+                    );
+                    None
+                }
+            },
+        }
+    }
+
+    fn fetch_price_per_btc(url: &str, block_height: u32) -> Result<Decimal, FiatError> {
+        let full_url = format!("{url}?height={block_height}");
+        let resp: serde_json::Value = ureq::get(&full_url)
+            .call()
+            .map_err(|e| FiatError::OracleUnreachable(e.to_string()))?
+            .into_json()
+            .map_err(|e| FiatError::OracleUnreachable(e.to_string()))?;
+        let price = resp
+            .get("price")
+            .and_then(|p| p.as_f64())
+            .ok_or_else(|| {
+                FiatError::OracleUnreachable("missing `price` field in oracle response".to_owned())
+            })?;
+        Decimal::try_from(price).map_err(|_| FiatError::Overflow)
+    }
+}
@@ -13,11 +13,7 @@ use clap::{CommandFactory, Parser, Subcommand};
 use cln_plugin::options::{
     ConfigOption, DefaultStringConfigOption, IntegerConfigOption, StringConfigOption,
 };
-use cln_rpc::model::requests::DatastoreMode;
-use cln_rpc::{
-    model::requests::{DatastoreRequest, ListdatastoreRequest},
-    ClnRpc, Request, Response,
-};
+use cln_rpc::{model::requests::ListdatastoreRequest, ClnRpc, Request, Response};
 use home::home_dir;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -27,9 +23,16 @@ use std::ffi::OsString;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, Mutex};
 
-use smaug::wallet::{AddArgs, DescriptorWallet, SMAUG_DATADIR, UTXO_DEPOSIT_TAG, UTXO_SPEND_TAG};
+use bdk_electrum::electrum_client;
+use bdk_esplora::esplora_client;
+use smaug::event_server;
+use smaug::fiat::{sats_to_fiat, PriceOracle};
+use smaug::wallet::{
+    get_esplora_url, AddArgs, Backend, DescriptorWallet, WalletStore, ONCHAIN_FEE_TAG, REORG_TAG,
+    SMAUG_DATADIR, SMAUG_DATASTORE_KEY, UTXO_DEPOSIT_TAG, UTXO_SPEND_TAG,
+};
 
 use cln_plugin::{anyhow, messages, Builder, Error, Plugin};
 use tokio;
@@ -60,6 +63,34 @@ const OPT_SMAUG_BRPC_COOKIE_DIR: StringConfigOption = ConfigOption::new_str_no_d
     "smaug_brpc_cookie_dir",
     "Bitcoind data directory (for cookie file access)",
 );
+const OPT_SMAUG_MIN_RESYNC_INTERVAL: IntegerConfigOption = ConfigOption::new_i64_no_default(
+    "smaug_min_resync_interval",
+    "Minimum number of seconds between resyncing the same wallet on block_added (default: always resync)",
+);
+const OPT_SMAUG_FIAT_ORACLE_URL: StringConfigOption = ConfigOption::new_str_no_default(
+    "smaug_fiat_oracle_url",
+    "URL of a price oracle returning {\"price\": <fiat-per-BTC>} for a given ?height=<n>, used to attach fiat fields to coin-movement notifications",
+);
+const OPT_SMAUG_FIAT_CURRENCY: StringConfigOption = ConfigOption::new_str_no_default(
+    "smaug_fiat_currency",
+    "Fiat currency code (e.g. usd) to quote prices in. Required alongside smaug_fiat_oracle_url to enable fiat fields",
+);
+const OPT_SMAUG_BACKEND: StringConfigOption = ConfigOption::new_str_no_default(
+    "smaug_backend",
+    "Default chain data backend for wallets that don't set their own source: bitcoind (default), esplora, or electrum",
+);
+const OPT_SMAUG_ESPLORA_URL: StringConfigOption = ConfigOption::new_str_no_default(
+    "smaug_esplora_url",
+    "Esplora base URL to use when smaug_backend=esplora (defaults to a known public instance for the network if unset)",
+);
+const OPT_SMAUG_ELECTRUM_URL: StringConfigOption = ConfigOption::new_str_no_default(
+    "smaug_electrum_url",
+    "Electrum server URL to use when smaug_backend=electrum; required when smaug_backend=electrum is set",
+);
+const OPT_SMAUG_EVENT_LISTEN: StringConfigOption = ConfigOption::new_str_no_default(
+    "smaug_event_listen",
+    "host:port to bind a WebSocket server streaming utxo_deposit/utxo_spent events to external subscribers; unset (default) disables it",
+);
 
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
@@ -76,13 +107,32 @@ async fn main() -> Result<(), anyhow::Error> {
         .option(OPT_SMAUG_BRPC_USER)
         .option(OPT_SMAUG_BRPC_PASS)
         .option(OPT_SMAUG_BRPC_COOKIE_DIR)
+        .option(OPT_SMAUG_MIN_RESYNC_INTERVAL)
+        .option(OPT_SMAUG_FIAT_ORACLE_URL)
+        .option(OPT_SMAUG_FIAT_CURRENCY)
+        .option(OPT_SMAUG_BACKEND)
+        .option(OPT_SMAUG_ESPLORA_URL)
+        .option(OPT_SMAUG_ELECTRUM_URL)
+        .option(OPT_SMAUG_EVENT_LISTEN)
         .notification(messages::NotificationTopic::new(UTXO_DEPOSIT_TAG))
         .notification(messages::NotificationTopic::new(UTXO_SPEND_TAG))
+        .notification(messages::NotificationTopic::new(ONCHAIN_FEE_TAG))
+        .notification(messages::NotificationTopic::new(REORG_TAG))
         .rpcmethod(
             "smaug",
             "Watch one or more external wallet descriptors and emit notifications when coins are moved",
             parse_command,
         )
+        .rpcmethod(
+            "smaug-balance",
+            "Show the unspent balance tracked for a watched descriptor wallet (unconfirmed outputs included)",
+            balance_rpc,
+        )
+        .rpcmethod(
+            "smaug-listutxos",
+            "List the UTXOs tracked for a watched descriptor wallet",
+            list_utxos_rpc,
+        )
         .subscribe("block_added", block_added_handler)
         .dynamic();
     let configured_plugin = if let Some(cp) = builder.configure().await? {
@@ -106,69 +156,122 @@ async fn main() -> Result<(), anyhow::Error> {
         Some(smaug_network) => smaug_network.as_str().to_owned(),
         None => cln_network.clone(),
     };
-    let brpc_host = configured_plugin.option(&OPT_SMAUG_BRPC_HOST).unwrap();
-    let brpc_port: u16 = match configured_plugin.option(&OPT_SMAUG_BRPC_PORT).unwrap() {
-        Some(sbp) => u16::try_from(sbp)?,
-        None => match network.as_str() {
-            "regtest" => 18443,
-            "signet" | "mutinynet" => 38332,
-            _ => 8332,
-        },
-    };
-    let mut brpc_auth: Auth = Auth::None;
-    if let Some(bu_val) = configured_plugin.option(&OPT_SMAUG_BRPC_USER).unwrap() {
-        if let Some(bs_val) = configured_plugin.option(&OPT_SMAUG_BRPC_PASS).unwrap() {
-            brpc_auth = Auth::UserPass(bu_val, bs_val);
-        }
-        if let Auth::None = brpc_auth {
-            return Err(anyhow!(
-                "specified `smaug_brpc_user` but did not specify `smaug_brpc_pass`"
-            ));
-        }
-    }
+    let smaug_backend = configured_plugin
+        .option(&OPT_SMAUG_BACKEND)
+        .unwrap()
+        .unwrap_or_else(|| "bitcoind".to_owned());
+    let backend = match smaug_backend.as_str() {
+        "bitcoind" | "core" => {
+            let brpc_host = configured_plugin.option(&OPT_SMAUG_BRPC_HOST).unwrap();
+            let brpc_port: u16 = match configured_plugin.option(&OPT_SMAUG_BRPC_PORT).unwrap() {
+                Some(sbp) => u16::try_from(sbp)?,
+                None => match network.as_str() {
+                    "regtest" => 18443,
+                    "signet" | "mutinynet" => 38332,
+                    _ => 8332,
+                },
+            };
+            let mut brpc_auth: Auth = Auth::None;
+            if let Some(bu_val) = configured_plugin.option(&OPT_SMAUG_BRPC_USER).unwrap() {
+                if let Some(bs_val) = configured_plugin.option(&OPT_SMAUG_BRPC_PASS).unwrap() {
+                    brpc_auth = Auth::UserPass(bu_val, bs_val);
+                }
+                if let Auth::None = brpc_auth {
+                    return Err(anyhow!(
+                        "specified `smaug_brpc_user` but did not specify `smaug_brpc_pass`"
+                    ));
+                }
+            }
 
-    if let Auth::None = brpc_auth {
-        if let Some(smaug_brpc_cookie_dir) = configured_plugin
-            .option(&OPT_SMAUG_BRPC_COOKIE_DIR)
-            .unwrap()
-        {
-            let cf_path = PathBuf::from(&smaug_brpc_cookie_dir).join(".cookie");
-            if !cf_path.exists() {
-                return Err(anyhow!(
-                    "Nonexistent cookie file specified in smaug_brpc_cookie_dir: {}",
-                    cf_path.display()
-                ));
+            if let Auth::None = brpc_auth {
+                if let Some(smaug_brpc_cookie_dir) = configured_plugin
+                    .option(&OPT_SMAUG_BRPC_COOKIE_DIR)
+                    .unwrap()
+                {
+                    let cf_path = PathBuf::from(&smaug_brpc_cookie_dir).join(".cookie");
+                    if !cf_path.exists() {
+                        return Err(anyhow!(
+                            "Nonexistent cookie file specified in smaug_brpc_cookie_dir: {}",
+                            cf_path.display()
+                        ));
+                    }
+                    brpc_auth =
+                        Auth::CookieFile(PathBuf::from(&smaug_brpc_cookie_dir).join(".cookie"));
+                } else {
+                    let cf_path = home_dir()
+                        .expect("cannot determine home dir")
+                        .join(format!(".bitcoin/{}", cln_network.clone()))
+                        .join(".cookie");
+                    if cf_path.exists() {
+                        brpc_auth = Auth::CookieFile(cf_path);
+                    }
+                }
             }
-            brpc_auth = Auth::CookieFile(PathBuf::from(&smaug_brpc_cookie_dir).join(".cookie"));
-        } else {
-            let cf_path = home_dir()
-                .expect("cannot determine home dir")
-                .join(format!(".bitcoin/{}", cln_network.clone()))
-                .join(".cookie");
-            if cf_path.exists() {
-                brpc_auth = Auth::CookieFile(cf_path);
+            if let Auth::None = brpc_auth {
+                return Err(anyhow!("must specify either `smaug_bprc_cookie_dir` or `smaug_brpc_user` and `smaug_brpc_pass`"));
+            }
+            if log::log_enabled!(log::Level::Debug) {
+                eprintln!("using auth info: {:?}", brpc_auth);
+            }
+            let rpc_client = Client::new(
+                &format!("http://{}:{}", brpc_host.clone(), brpc_port.clone()),
+                brpc_auth.clone(),
+            )?;
+            let _ = match rpc_client.get_connection_count() {
+                Ok(cc) => cc,
+                Err(e) => {
+                    return Err(anyhow!("Cannot connect to bitcoind, ensure your `smaug_bprc_cookie_dir` or `smaug_brpc_user` and `smaug_brpc_pass` are correct
+                        and that your node is active and accepting rpc connections"))
+                },
+            };
+            Backend::BitcoindRpc {
+                host: brpc_host,
+                port: brpc_port,
+                auth: brpc_auth,
             }
         }
-    }
-    if let Auth::None = brpc_auth {
-        return Err(anyhow!("must specify either `smaug_bprc_cookie_dir` or `smaug_brpc_user` and `smaug_brpc_pass`"));
-    } else {
-        if log::log_enabled!(log::Level::Debug) {
-            eprintln!("using auth info: {:?}", brpc_auth);
+        "esplora" => {
+            let base_url = match configured_plugin.option(&OPT_SMAUG_ESPLORA_URL).unwrap() {
+                Some(url) => url,
+                None => get_esplora_url(network.as_str()),
+            };
+            // Backend-aware connectivity check, mirroring the bitcoind path's
+            // `get_connection_count`: a reachable-but-wrong URL (typo, wrong
+            // network) should fail plugin startup, not the first wallet sync.
+            let client = esplora_client::Builder::new(&base_url).build_blocking();
+            let _ = client.get_height().map_err(|e| {
+                anyhow!(
+                    "Cannot connect to Esplora server at {}: {}",
+                    base_url,
+                    e
+                )
+            })?;
+            Backend::Esplora { base_url }
         }
-        let rpc_client = Client::new(
-            &format!("http://{}:{}", brpc_host.clone(), brpc_port.clone()),
-            brpc_auth.clone(),
-        )?;
-
-        let _ = match rpc_client.get_connection_count() {
-            Ok(cc) => cc,
-            Err(e) => {
-                return Err(anyhow!("Cannot connect to bitcoind, ensure your `smaug_bprc_cookie_dir` or `smaug_brpc_user` and `smaug_brpc_pass` are correct 
-                    and that your node is active and accepting rpc connections"))
-            },
-        };
-    }
+        "electrum" => {
+            let url = match configured_plugin.option(&OPT_SMAUG_ELECTRUM_URL).unwrap() {
+                Some(url) => url,
+                None => {
+                    return Err(anyhow!(
+                        "smaug_backend=electrum requires smaug_electrum_url to be set"
+                    ))
+                }
+            };
+            // Backend-aware connectivity check, mirroring the esplora path's
+            // `get_height`: a reachable-but-wrong URL (typo, wrong network)
+            // should fail plugin startup, not the first wallet sync.
+            let _ = electrum_client::Client::new(&url).map_err(|e| {
+                anyhow!("Cannot connect to Electrum server at {}: {}", url, e)
+            })?;
+            Backend::Electrum { url }
+        }
+        other => {
+            return Err(anyhow!(
+                "invalid smaug_backend '{}': expected bitcoind, esplora, or electrum",
+                other
+            ))
+        }
+    };
 
     let ln_dir: PathBuf = configured_plugin.configuration().lightning_dir.into();
     // Create data dir if it does not exist
@@ -185,7 +288,7 @@ async fn main() -> Result<(), anyhow::Error> {
 
     let lds_response = rpc
         .call(Request::ListDatastore(ListdatastoreRequest {
-            key: Some(vec!["smaug".to_owned()]),
+            key: Some(vec![SMAUG_DATASTORE_KEY.to_owned()]),
         }))
         .await
         .map_err(|e| anyhow!("Error calling listdatastore: {:?}", e))?;
@@ -194,8 +297,8 @@ async fn main() -> Result<(), anyhow::Error> {
         Response::ListDatastore(r) => match r.datastore.is_empty() {
             true => BTreeMap::new(),
             false => match &r.datastore[0].string {
-                Some(deserialized) => match serde_json::from_str(&deserialized) {
-                    core::result::Result::Ok(dws) => dws,
+                Some(deserialized) => match WalletStore::from_datastore_str(deserialized) {
+                    core::result::Result::Ok(store) => store.wallets,
                     core::result::Result::Err(e) => {
                         // sometimes log::error! doesn't execute before plugin is killed, so we use eprintln! here instead
                         eprintln!(
@@ -214,13 +317,22 @@ async fn main() -> Result<(), anyhow::Error> {
         _ => panic!("Unrecognized type returned from listdatastore call, exiting"),
     };
     log::trace!("creating plugin state");
+    let min_resync_interval_secs = configured_plugin
+        .option(&OPT_SMAUG_MIN_RESYNC_INTERVAL)
+        .unwrap()
+        .map(|v| v as u64)
+        .unwrap_or(0);
+    let fiat_oracle_url = configured_plugin.option(&OPT_SMAUG_FIAT_ORACLE_URL).unwrap();
+    let fiat_currency = configured_plugin.option(&OPT_SMAUG_FIAT_CURRENCY).unwrap();
+    let (event_tx, _) = broadcast::channel(256);
     let watch_descriptor = Smaug {
         wallets,
         network: network.clone(),
-        brpc_host: brpc_host.clone(),
-        brpc_port: brpc_port.clone(),
-        brpc_auth: brpc_auth.clone(),
+        backend,
         db_dir: ln_dir.join(SMAUG_DATADIR),
+        min_resync_interval_secs,
+        fiat_oracle: PriceOracle::new(fiat_oracle_url, fiat_currency),
+        event_tx: event_tx.clone(),
     };
     let plugin_state = Arc::new(Mutex::new(watch_descriptor.clone()));
     log::trace!("getting lock on state");
@@ -228,6 +340,14 @@ async fn main() -> Result<(), anyhow::Error> {
     plugin_state.lock().await.network = network;
     log::trace!("starting Smaug");
 
+    if let Some(listen_addr) = configured_plugin.option(&OPT_SMAUG_EVENT_LISTEN).unwrap() {
+        tokio::spawn(async move {
+            if let Err(e) = event_server::serve(&listen_addr, event_tx).await {
+                log::error!("smaug_event_listen server error: {:?}", e);
+            }
+        });
+    }
+
     let plugin = configured_plugin.start(plugin_state).await?;
     log::info!("Smaug started");
     plugin.join().await
@@ -261,7 +381,22 @@ enum Commands {
     },
     /// List descriptor wallets currently being watched
     #[command(alias = "list")]
-    Ls,
+    Ls {
+        /// Render a human-readable table instead of JSON
+        #[arg(long)]
+        human: bool,
+    },
+    /// Rewind a watched descriptor wallet and re-derive its transactions
+    /// from a chosen height (or its birthday, if unset); only transactions
+    /// not already recorded for the wallet are notified
+    #[command(alias = "resync")]
+    Rescan {
+        /// Deterministic name (concatenated checksums) of wallet to rescan
+        descriptor_name: String,
+        /// Block height to rescan from. Defaults to the wallet's birthday,
+        /// or a full stop-gap recovery scan if no birthday is set either.
+        height: Option<u32>,
+    },
 }
 
 fn to_os_string(v: Value) -> OsString {
@@ -290,7 +425,11 @@ async fn parse_command(
                     Commands::Rm { descriptor_name } => {
                         return delete(plugin, descriptor_name).await
                     }
-                    Commands::Ls => return list(plugin).await,
+                    Commands::Ls { human } => return list(plugin, human).await,
+                    Commands::Rescan {
+                        descriptor_name,
+                        height,
+                    } => return rescan(plugin, descriptor_name, height).await,
                 },
                 None => {
                     let help_json = json!({
@@ -327,19 +466,18 @@ async fn add(plugin: Plugin<State>, args: AddArgs) -> Result<serde_json::Value,
     let mut dw = DescriptorWallet::from_args(args, plugin.state().lock().await.network.clone())
         .map_err(|e| anyhow!("error parsing args: {}", e))?;
     log::trace!("params = {:?}", dw);
-    let (db_dir, brpc_host, brpc_port, brpc_auth) = {
+    let (db_dir, backend) = {
         let state = plugin.state().lock().await;
-        (
-            state.db_dir.clone(),
-            // FIXME: actually use the RpcConnection struct instead of this nonsense
-            state.brpc_host.clone(),
-            state.brpc_port.clone(),
-            state.brpc_auth.clone(),
-        )
+        (state.db_dir.clone(), state.backend.clone())
     };
+    // AddArgs has no per-wallet `source` override yet, so every newly added
+    // wallet follows the plugin-wide default backend.
+    dw = dw
+        .with_chain_source(backend.default_chain_source())
+        .map_err(|e| anyhow!("error applying default chain source: {}", e))?;
     let mut dw_clone = dw.clone();
     let wallet = dw_clone
-        .fetch_wallet(db_dir, brpc_host, brpc_port, brpc_auth)
+        .fetch_wallet(db_dir, backend.brpc_host(), backend.brpc_port(), backend.brpc_auth())
         .await?;
     let bdk_transactions_iter = wallet.transactions();
     let mut transactions = Vec::<CanonicalTx<'_, Transaction, ConfirmationTimeAnchor>>::new();
@@ -348,39 +486,33 @@ async fn add(plugin: Plugin<State>, args: AddArgs) -> Result<serde_json::Value,
         transactions.push(bdk_transaction);
     }
 
+    dw.notify_reorgs(&plugin, &transactions).await?;
+    let mut fiat_oracle = plugin.state().lock().await.fiat_oracle.clone();
+    let event_tx = plugin.state().lock().await.event_tx.clone();
     if transactions.len() > 0 {
         log::trace!("found some transactions: {:?}", transactions);
         let new_txs = dw.update_transactions(transactions);
         if new_txs.len() > 0 {
             for tx in new_txs {
                 log::trace!("new tx found!: {:?}", tx);
-                dw.send_notifications_for_tx(&plugin, &wallet, tx).await?;
+                dw.update_utxos(&wallet, &tx);
+                dw.send_notifications_for_tx(&plugin, &event_tx, &wallet, tx, &mut fiat_oracle)
+                    .await?;
             }
         } else {
             log::debug!("no new txs this time");
         }
     }
+    plugin.state().lock().await.fiat_oracle = fiat_oracle;
     // FIXME: this is horrible, please find a better way to do this
     dw.update_last_synced(dw_clone.last_synced.unwrap());
     log::trace!("waiting for wallet lock");
     plugin.state().lock().await.add_descriptor_wallet(&dw)?;
 
     log::trace!("add_descriptor_wallet");
-    let wallets_str = json!(plugin.state().lock().await.wallets).to_string();
     let rpc_file = plugin.configuration().rpc_file;
     let p = Path::new(&rpc_file);
-
-    let mut rpc = ClnRpc::new(p).await?;
-    let _ds_response = rpc
-        .call(Request::Datastore(DatastoreRequest {
-            key: vec!["smaug".to_owned()],
-            string: Some(wallets_str),
-            hex: None,
-            mode: Some(DatastoreMode::CREATE_OR_REPLACE),
-            generation: None,
-        }))
-        .await
-        .map_err(|e| anyhow!("Error calling listdatastore: {:?}", e))?;
+    plugin.state().lock().await.persist_wallets(p).await?;
     let name = &dw.get_name()?;
     let message = format!("Wallet with deterministic name {} successfully added", name);
     log::info!("{}", message);
@@ -395,56 +527,242 @@ struct ListResponseItem {
     pub birthday: Option<u32>,
     pub gap: Option<u32>,
     pub network: Option<String>,
+    /// Fiat value of `balance`, at the oracle's rate for this wallet's
+    /// current sync height, or at the last cached rate if the oracle is
+    /// unreachable (`fiat_rate_height` says which height's rate was used).
+    /// `None` when fiat valuation isn't configured
+    /// (`smaug_fiat_oracle_url`/`smaug_fiat_currency`) or the oracle is
+    /// unreachable and no rate has been cached yet.
+    pub fiat_balance: Option<String>,
+    pub fiat_currency: Option<String>,
+    pub fiat_rate_height: Option<u32>,
 }
 
-async fn list(plugin: Plugin<State>) -> Result<serde_json::Value, Error> {
-    let state = &plugin.state().lock().await;
+/// Truncates `s` to at most `max_len` characters, appending `...` if it was
+/// cut short, so a long descriptor doesn't blow out the table's width.
+fn truncate_for_table(s: &str, max_len: usize) -> String {
+    if s.chars().count() <= max_len {
+        s.to_owned()
+    } else {
+        format!(
+            "{}...",
+            s.chars().take(max_len.saturating_sub(3)).collect::<String>()
+        )
+    }
+}
 
-    let wallets = state.wallets.clone();
-    let mut result = BTreeMap::<String, ListResponseItem>::new();
-    let (db_dir, brpc_host, brpc_port, brpc_auth) = {
+/// Renders `result` as a fixed-width table for interactive use via
+/// `smaug ls --human`, instead of raw JSON.
+fn render_wallets_table(result: &BTreeMap<String, ListResponseItem>) -> String {
+    const DESCRIPTOR_WIDTH: usize = 40;
+    let mut out = format!(
+        "{:<34} {:<8} {:>14} {:>10} {:>6} {:>14} {:<8}  {:<40}\n",
+        "name", "network", "balance", "birthday", "gap", "fiat_balance", "currency", "descriptor"
+    );
+    for (name, item) in result {
+        out.push_str(&format!(
+            "{:<34} {:<8} {:>14} {:>10} {:>6} {:>14} {:<8}  {:<40}\n",
+            name,
+            item.network.as_deref().unwrap_or("-"),
+            item.balance,
+            item.birthday
+                .map(|b| b.to_string())
+                .unwrap_or_else(|| "-".to_owned()),
+            item.gap
+                .map(|g| g.to_string())
+                .unwrap_or_else(|| "-".to_owned()),
+            item.fiat_balance.as_deref().unwrap_or("-"),
+            item.fiat_currency.as_deref().unwrap_or("-"),
+            truncate_for_table(&item.descriptor, DESCRIPTOR_WIDTH),
+        ));
+    }
+    out
+}
+
+async fn list(plugin: Plugin<State>, human: bool) -> Result<serde_json::Value, Error> {
+    let (wallets, db_dir, backend, mut fiat_oracle) = {
+        let state = plugin.state().lock().await;
         (
+            state.wallets.clone(),
             state.db_dir.clone(),
-            state.brpc_host.clone(),
-            state.brpc_port.clone(),
-            state.brpc_auth.clone(),
+            state.backend.clone(),
+            state.fiat_oracle.clone(),
         )
     };
+    let mut result = BTreeMap::<String, ListResponseItem>::new();
 
     for (wallet_name, wallet) in wallets {
         let mut dw_clone = wallet.clone();
         let bdk_wallet = dw_clone
             .fetch_wallet(
                 db_dir.clone(),
-                brpc_host.clone(),
-                brpc_port.clone(),
-                brpc_auth.clone(),
+                backend.brpc_host(),
+                backend.brpc_port(),
+                backend.brpc_auth(),
             )
             .await?;
 
+        let balance = bdk_wallet.get_balance().total();
+        let rate = bdk_wallet
+            .latest_checkpoint()
+            .and_then(|cp| fiat_oracle.rate_at(cp.block_id().height));
+        let (fiat_balance, fiat_currency, fiat_rate_height) = match &rate {
+            Some(rate) => match sats_to_fiat(balance, rate) {
+                Ok(amount) => (
+                    Some(amount.to_string()),
+                    Some(rate.currency.clone()),
+                    Some(rate.block_height),
+                ),
+                Err(e) => {
+                    log::warn!("skipping fiat_balance for {}: {}", wallet_name, e);
+                    (None, None, None)
+                }
+            },
+            None => (None, None, None),
+        };
+
         result.insert(
             wallet_name.clone(),
             ListResponseItem {
                 descriptor: wallet.descriptor.clone(),
                 change_descriptor: wallet.change_descriptor.clone(),
-                balance: bdk_wallet.get_balance().total(),
+                balance,
                 birthday: wallet.birthday.clone(),
                 gap: wallet.gap.clone(),
                 network: wallet.network.clone(),
+                fiat_balance,
+                fiat_currency,
+                fiat_rate_height,
             },
         );
     }
+    plugin.state().lock().await.fiat_oracle = fiat_oracle;
+    if human {
+        return Ok(json!({
+            "table": render_wallets_table(&result),
+            "format-hint": "simple",
+        }));
+    }
     Ok(json!(result))
 }
 
+async fn balance_rpc(
+    plugin: Plugin<State>,
+    v: serde_json::Value,
+) -> Result<serde_json::Value, Error> {
+    let descriptor_name = parse_single_string_arg(&v)?;
+    let state = plugin.state().lock().await;
+    let dw = state
+        .wallets
+        .get(&descriptor_name)
+        .ok_or_else(|| anyhow!("Can't find wallet '{}'.", descriptor_name))?;
+    Ok(json!({"name": descriptor_name, "balance": dw.balance()}))
+}
+
+async fn list_utxos_rpc(
+    plugin: Plugin<State>,
+    v: serde_json::Value,
+) -> Result<serde_json::Value, Error> {
+    let arg_vec = match v {
+        serde_json::Value::Array(a) => a,
+        _ => return Err(anyhow!("only positional args supported. no keyword args.")),
+    };
+    let descriptor_name = arg_vec
+        .get(0)
+        .and_then(|a| a.as_str())
+        .ok_or_else(|| anyhow!("descriptor_name is required"))?
+        .to_owned();
+    let include_spent = arg_vec
+        .get(1)
+        .and_then(|a| a.as_bool())
+        .unwrap_or(false);
+    let state = plugin.state().lock().await;
+    let dw = state
+        .wallets
+        .get(&descriptor_name)
+        .ok_or_else(|| anyhow!("Can't find wallet '{}'.", descriptor_name))?;
+    let utxos: BTreeMap<&String, &smaug::wallet::Utxo> =
+        dw.utxos(include_spent).into_iter().collect();
+    Ok(json!({"name": descriptor_name, "utxos": utxos}))
+}
+
+fn parse_single_string_arg(v: &serde_json::Value) -> Result<String, Error> {
+    let arg_vec = match v {
+        serde_json::Value::Array(a) => a,
+        _ => return Err(anyhow!("only positional args supported. no keyword args.")),
+    };
+    arg_vec
+        .get(0)
+        .and_then(|a| a.as_str())
+        .map(|s| s.to_owned())
+        .ok_or_else(|| anyhow!("descriptor_name is required"))
+}
+
+/// Rewinds an already-watched wallet's `last_synced` to `height` (defaulting
+/// to the wallet's `birthday`, or falling back to a full stop-gap recovery
+/// scan if neither is set) and re-runs `fetch_wallet`, which re-derives and
+/// re-applies every transaction from that point forward. Emits the normal
+/// deposit/spend notifications for anything newly observed.
+async fn rescan(
+    plugin: Plugin<State>,
+    descriptor_name: String,
+    height: Option<u32>,
+) -> Result<serde_json::Value, Error> {
+    let mut dw = plugin
+        .state()
+        .lock()
+        .await
+        .wallets
+        .get(&descriptor_name)
+        .ok_or_else(|| anyhow!("Can't find wallet '{}'.", descriptor_name))?
+        .clone();
+    dw.last_synced = height.or(dw.birthday);
+
+    let (db_dir, backend) = {
+        let state = plugin.state().lock().await;
+        (state.db_dir.clone(), state.backend.clone())
+    };
+    let mut dw_clone = dw.clone();
+    let wallet = dw_clone
+        .fetch_wallet(db_dir, backend.brpc_host(), backend.brpc_port(), backend.brpc_auth())
+        .await?;
+
+    let mut fiat_oracle = plugin.state().lock().await.fiat_oracle.clone();
+    let event_tx = plugin.state().lock().await.event_tx.clone();
+    let bdk_transactions_iter = wallet.transactions();
+    let mut transactions = Vec::<CanonicalTx<'_, Transaction, ConfirmationTimeAnchor>>::new();
+    for bdk_transaction in bdk_transactions_iter {
+        transactions.push(bdk_transaction);
+    }
+    dw.notify_reorgs(&plugin, &transactions).await?;
+    if transactions.len() > 0 {
+        let new_txs = dw.update_transactions(transactions);
+        for tx in new_txs {
+            dw.update_utxos(&wallet, &tx);
+            dw.send_notifications_for_tx(&plugin, &event_tx, &wallet, tx, &mut fiat_oracle)
+                .await?;
+        }
+    }
+    plugin.state().lock().await.fiat_oracle = fiat_oracle;
+
+    dw.update_last_synced(dw_clone.last_synced.unwrap());
+    plugin.state().lock().await.add_descriptor_wallet(&dw)?;
+
+    let rpc_file = plugin.configuration().rpc_file;
+    let p = Path::new(&rpc_file);
+    plugin.state().lock().await.persist_wallets(p).await?;
+
+    let message = format!("Rescanned wallet {} up to height {:?}", descriptor_name, dw.last_synced);
+    log::info!("{}", message);
+    Ok(json!({"name": descriptor_name, "message": message}))
+}
+
 async fn delete(
     plugin: Plugin<State>,
     descriptor_name: String,
 ) -> Result<serde_json::Value, Error> {
     let db_dir_path = plugin.state().lock().await.db_dir.clone();
-    let wallets = &mut plugin.state().lock().await.wallets;
-
-    let removed_item = wallets.remove(&descriptor_name);
+    let removed_item = plugin.state().lock().await.wallets.remove(&descriptor_name);
     let db_file_path = match removed_item {
         Some(dw) => match dw.get_db_file_path(db_dir_path) {
             Ok(dw) => dw,
@@ -456,54 +774,122 @@ async fn delete(
     log::debug!("Deleted smaug db file at {}", db_file_path);
     let rpc_file = plugin.configuration().rpc_file;
     let p = Path::new(&rpc_file);
-
-    let mut rpc = ClnRpc::new(p).await?;
-    let _ds_response = rpc
-        .call(Request::Datastore(DatastoreRequest {
-            key: vec!["smaug".to_owned()],
-            string: Some(json!(wallets).to_string()),
-            hex: None,
-            mode: Some(DatastoreMode::CREATE_OR_REPLACE),
-            generation: None,
-        }))
-        .await
-        .map_err(|e| anyhow!("Error calling listdatastore: {:?}", e))?;
+    plugin.state().lock().await.persist_wallets(p).await?;
 
     Ok(json!(format!("Deleted wallet: {}", descriptor_name)))
 }
 
+/// The `block_added` notification's payload: `{"block_added": {"hash":
+/// "...", "height": N}}`.
+#[derive(Debug, Deserialize)]
+struct BlockAddedNotification {
+    block_added: BlockAddedPayload,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlockAddedPayload {
+    hash: bdk::bitcoin::BlockHash,
+    height: u32,
+}
+
 async fn block_added_handler(plugin: Plugin<State>, v: serde_json::Value) -> Result<(), Error> {
     log::trace!("Got a block_added notification: {}", v);
     log::trace!(
         "Smaug state!!! {:?}",
         plugin.state().lock().await.wallets.clone()
     );
-    let (db_dir, brpc_host, brpc_port, brpc_auth) = {
+    let block_added: BlockAddedNotification = serde_json::from_value(v)?;
+    let height = block_added.block_added.height;
+    let block_hash = block_added.block_added.hash;
+
+    // Clone everything this handler needs out from under the lock up front,
+    // the same way `add()`/`rescan()` do: the sync/notify work below issues
+    // blocking bitcoind RPC calls and (via `send_notifications_for_tx`) a
+    // blocking fiat-oracle HTTP request per wallet, so holding `Smaug`'s
+    // `Mutex` across the whole per-wallet loop would stall every other
+    // plugin RPC (`smaug add/ls/rescan/rm/balance`) for as long as that
+    // takes. The lock is only reacquired afterwards, to merge each wallet's
+    // updated state back in and persist.
+    let (db_dir, backend, min_resync_interval_secs, event_tx, mut fiat_oracle, mut descriptor_wallets) = {
         let state = plugin.state().lock().await;
         (
             state.db_dir.clone(),
-            state.brpc_host.clone(),
-            state.brpc_port.clone(),
-            state.brpc_auth.clone(),
+            state.backend.clone(),
+            state.min_resync_interval_secs,
+            state.event_tx.clone(),
+            state.fiat_oracle.clone(),
+            state.wallets.clone(),
         )
     };
 
-    log::trace!("waiting for wallet lock in block_handler");
-    let state = &mut plugin.state().lock().await;
-    let descriptor_wallets = &mut state.wallets;
-
     log::trace!("db_dir in block_handler: {:?}", &db_dir);
-    log::trace!("acquired wallet lock in block_handler");
+    let fiat_oracle = &mut fiat_oracle;
     for (_dw_desc, dw) in descriptor_wallets.iter_mut() {
-        log::trace!("fetching wallet in block_handler: {:?}", dw);
+        if !dw.should_sync_now(min_resync_interval_secs) {
+            log::debug!(
+                "skipping resync for {:?}, synced too recently (smaug_min_resync_interval={}s)",
+                dw.descriptor,
+                min_resync_interval_secs
+            );
+            continue;
+        }
+        dw.update_last_sync_attempt();
+
+        if dw.is_next_block(height) {
+            log::trace!("applying connected block {} directly in block_handler", height);
+            let mut dw_clone = dw.clone();
+            let synced = dw_clone
+                .sync_single_block(
+                    db_dir.clone(),
+                    backend.brpc_host(),
+                    backend.brpc_port(),
+                    backend.brpc_auth(),
+                    height,
+                    block_hash,
+                )
+                .await?;
+
+            // `None` means the fetched block doesn't chain onto our current
+            // tip -- a reorg happened underneath us since `last_synced` --
+            // so fall through to the full-rescan path below instead of
+            // applying it blind; that path runs `notify_reorgs`, this one
+            // doesn't.
+            if let Some((wallet, block_txids)) = synced {
+                let new_block_txs: Vec<CanonicalTx<'_, Transaction, ConfirmationTimeAnchor>> =
+                    wallet
+                        .transactions()
+                        .filter(|tx| block_txids.contains(&tx.tx_node.txid))
+                        .collect();
+                if new_block_txs.len() > 0 {
+                    log::trace!(
+                        "found some new transactions in new block! : {:?}",
+                        new_block_txs
+                    );
+                    let new_txs = dw.update_transactions(new_block_txs);
+                    for tx in new_txs {
+                        dw.update_utxos(&wallet, &tx);
+                        dw.send_notifications_for_tx(&plugin, &event_tx, &wallet, tx, fiat_oracle)
+                            .await?;
+                    }
+                } else {
+                    log::debug!("no new txs this time");
+                }
+                dw.update_last_synced(dw_clone.last_synced.unwrap());
+                continue;
+            }
+        }
 
+        log::trace!(
+            "wallet {:?} more than one block behind, not on a bitcoind chain source, or its fast-path tip check failed; falling back to full rescan",
+            dw.descriptor
+        );
         let mut dw_clone = dw.clone();
         let wallet = dw_clone
             .fetch_wallet(
                 db_dir.clone(),
-                brpc_host.clone(),
-                brpc_port.clone(),
-                brpc_auth.clone(),
+                backend.brpc_host(),
+                backend.brpc_port(),
+                backend.brpc_auth(),
             )
             .await?;
 
@@ -515,6 +901,7 @@ async fn block_added_handler(plugin: Plugin<State>, v: serde_json::Value) -> Res
             transactions.push(bdk_transaction);
         }
 
+        dw.notify_reorgs(&plugin, &transactions).await?;
         if transactions.len() > 0 {
             log::trace!(
                 "found some new transactions in new block! : {:?}",
@@ -523,7 +910,9 @@ async fn block_added_handler(plugin: Plugin<State>, v: serde_json::Value) -> Res
             let new_txs = dw.update_transactions(transactions);
             if new_txs.len() > 0 {
                 for tx in new_txs {
-                    dw.send_notifications_for_tx(&plugin, &wallet, tx).await?;
+                    dw.update_utxos(&wallet, &tx);
+                    dw.send_notifications_for_tx(&plugin, &event_tx, &wallet, tx, fiat_oracle)
+                        .await?;
                 }
             } else {
                 log::debug!("no new txs this time");
@@ -536,6 +925,16 @@ async fn block_added_handler(plugin: Plugin<State>, v: serde_json::Value) -> Res
         // FIXME: this is horrible, please find a better way to do this
         dw.update_last_synced(dw_clone.last_synced.unwrap());
     }
+
+    log::trace!("waiting for wallet lock in block_handler to merge synced state back in");
+    let mut guard = plugin.state().lock().await;
+    for dw in descriptor_wallets.values() {
+        guard.add_descriptor_wallet(dw)?;
+    }
+    guard.fiat_oracle = fiat_oracle.clone();
+    let rpc_file = plugin.configuration().rpc_file;
+    let p = Path::new(&rpc_file);
+    guard.persist_wallets(p).await?;
     log::trace!("returning from block_added_handler");
     Ok(())
 }
@@ -1,10 +1,17 @@
-use std::{collections::BTreeMap, path::PathBuf, sync::Arc};
+use std::{collections::BTreeMap, path::Path, path::PathBuf, sync::Arc};
 
 use bdk::bitcoin;
-use bitcoincore_rpc::Auth;
-use tokio::sync::Mutex;
+use cln_rpc::model::requests::DatastoreMode;
+use cln_rpc::{model::requests::DatastoreRequest, ClnRpc, Request};
+use tokio::sync::{broadcast, Mutex};
 
-use crate::wallet::DescriptorWallet;
+use crate::fiat::PriceOracle;
+use crate::wallet::{Backend, DescriptorWallet, WalletStore, SMAUG_DATASTORE_KEY};
+
+/// Capacity of [`Smaug::event_tx`]'s broadcast channel: how many
+/// not-yet-delivered events a slow `smaug_event_listen` subscriber can fall
+/// behind by before it starts missing them (see `broadcast::error::Lagged`).
+const EVENT_CHANNEL_CAPACITY: usize = 256;
 
 pub type State = Arc<Mutex<Smaug>>;
 
@@ -14,17 +21,24 @@ pub struct Smaug {
     pub wallets: BTreeMap<String, DescriptorWallet>,
     /// The network relevant to our wallets
     pub network: String,
-    /// Bitcoind RPC host
-    pub brpc_host: String,
-    /// Bitcoind RPC port
-    pub brpc_port: u16,
-    // /// Bitcoind RPC user
-    // pub brpc_user: String,
-    // /// Bitcoind RPC password
-    // pub brpc_pass: String,
-    pub brpc_auth: Auth,
+    /// The chain data source wallets sync through by default, unless they
+    /// set their own `chain_source` override. Selected via `smaug_backend`.
+    pub backend: Backend,
     /// The db path relevant to our wallets
     pub db_dir: PathBuf,
+    /// Minimum number of seconds that must elapse between two sync attempts
+    /// for the same wallet, to avoid hammering the node on every block tick.
+    /// `0` (the default) means always resync.
+    pub min_resync_interval_secs: u64,
+    /// Fetches and caches the fiat price quotes attached to coin-movement
+    /// notifications. Unconfigured (the default) means notifications carry
+    /// no fiat fields at all.
+    pub fiat_oracle: PriceOracle,
+    /// Fan-out channel for `utxo_deposit`/`utxo_spent` events, in the same
+    /// payload shape sent as CLN custom notifications. The `smaug_event_listen`
+    /// server (see `event_server`) subscribes to this to stream events to
+    /// external processes; sending is a no-op when nobody is subscribed.
+    pub event_tx: broadcast::Sender<serde_json::Value>,
 }
 
 impl Smaug {
@@ -32,10 +46,15 @@ impl Smaug {
         Self {
             wallets: BTreeMap::new(),
             network: bitcoin::Network::Bitcoin.to_string(),
-            brpc_host: String::from("127.0.0.1"),
-            brpc_port: 8332,
-            brpc_auth: Auth::None,
+            backend: Backend::BitcoindRpc {
+                host: String::from("127.0.0.1"),
+                port: 8332,
+                auth: bitcoincore_rpc::Auth::None,
+            },
             db_dir: PathBuf::new(),
+            min_resync_interval_secs: 0,
+            fiat_oracle: PriceOracle::default(),
+            event_tx: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
         }
     }
 
@@ -47,4 +66,26 @@ impl Smaug {
         self.wallets.insert(wallet.get_name()?, wallet.clone());
         Ok(())
     }
+
+    /// Persists the current wallet set to the CLN `datastore` under a single
+    /// versioned blob, keyed by [`SMAUG_DATASTORE_KEY`].
+    ///
+    /// `CREATE_OR_REPLACE` is a single atomic datastore write on CLN's side,
+    /// so a crash mid-write either leaves the previous blob intact or the new
+    /// one fully committed -- it can never observe a half-written value.
+    pub async fn persist_wallets(&self, rpc_file: &Path) -> Result<(), anyhow::Error> {
+        let store = WalletStore::new(self.wallets.clone());
+        let store_str = serde_json::to_string(&store)?;
+        let mut rpc = ClnRpc::new(rpc_file).await?;
+        rpc.call(Request::Datastore(DatastoreRequest {
+            key: vec![SMAUG_DATASTORE_KEY.to_owned()],
+            string: Some(store_str),
+            hex: None,
+            mode: Some(DatastoreMode::CREATE_OR_REPLACE),
+            generation: None,
+        }))
+        .await
+        .map_err(|e| anyhow::anyhow!("Error persisting wallets to datastore: {:?}", e))?;
+        Ok(())
+    }
 }
@@ -8,25 +8,83 @@ use bdk::{
     wallet::wallet_name_from_descriptor,
     Wallet,
 };
+use bdk_electrum::{electrum_client, ElectrumExt};
+use bdk_esplora::{esplora_client, EsploraExt};
 use bdk_file_store::Store;
 use bitcoincore_rpc::{
     bitcoincore_rpc_json::{
         ScanBlocksOptions, ScanBlocksRequest, ScanBlocksRequestDescriptor, ScanBlocksResult,
     },
-    Auth, Client, RpcApi,
+    jsonrpc, Auth, Client, RpcApi,
 };
 use clap::{command, Parser};
 use cln_plugin::{Error, Plugin};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::{collections::BTreeMap, fmt, path::PathBuf, time::Duration};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+    path::PathBuf,
+};
+use tokio::sync::broadcast;
 
+use crate::brpc_auth::{build_rpc_client, is_auth_error};
+use crate::fiat::{sats_to_fiat, PriceOracle, Rate};
 use crate::state::State;
 
 pub const SMAUG_DATADIR: &str = ".smaug";
 
+/// Default number of unused addresses past the last-used index to derive and
+/// watch, when a wallet doesn't specify its own `gap`. Mirrors BDK's own
+/// `DEFAULT_LOOKAHEAD`.
+pub const DEFAULT_LOOKAHEAD: u32 = 25;
+
 pub const UTXO_DEPOSIT_TAG: &str = "utxo_deposit";
 pub const UTXO_SPENT_TAG: &str = "utxo_spent";
+pub const ONCHAIN_FEE_TAG: &str = "onchain_fee";
+pub const REORG_TAG: &str = "reorg";
+
+/// Datastore key under which the set of watched wallets is persisted.
+pub const SMAUG_DATASTORE_KEY: &str = "smaug";
+
+/// Schema version of [`WalletStore`]. Bump this whenever `DescriptorWallet`'s
+/// on-disk shape changes in a way that requires migration on load.
+pub const WALLET_STORE_VERSION: u32 = 1;
+
+/// Versioned, serializable snapshot of all watched wallets.
+///
+/// This is the shape actually written to the CLN `datastore`, as opposed to
+/// the bare `BTreeMap<String, DescriptorWallet>` used at runtime. Wrapping it
+/// with a `version` tag lets future changes to `DescriptorWallet` migrate old
+/// blobs instead of failing to deserialize outright.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct WalletStore {
+    pub version: u32,
+    pub wallets: BTreeMap<String, DescriptorWallet>,
+}
+
+impl WalletStore {
+    pub fn new(wallets: BTreeMap<String, DescriptorWallet>) -> Self {
+        Self {
+            version: WALLET_STORE_VERSION,
+            wallets,
+        }
+    }
+
+    /// Parses a `WalletStore` from whatever was stored under the datastore key,
+    /// migrating older schema versions as needed.
+    pub fn from_datastore_str(s: &str) -> Result<Self, serde_json::Error> {
+        // Version 1 is the only schema so far; if `version` is missing entirely,
+        // assume it's a pre-versioning blob (a bare wallet map) and wrap it.
+        match serde_json::from_str::<WalletStore>(s) {
+            Ok(store) => Ok(store),
+            Err(_) => {
+                let wallets: BTreeMap<String, DescriptorWallet> = serde_json::from_str(s)?;
+                Ok(WalletStore::new(wallets))
+            }
+        }
+    }
+}
 
 /// Errors related to the `smaug` command.
 #[derive(Debug)]
@@ -71,6 +129,82 @@ pub enum WDNetwork {
     Mutinynet,
 }
 
+/// The chain data source a watched descriptor syncs against.
+///
+/// `BitcoindScanBlocks` (the default) drives the existing `scanblocks`-based
+/// sync in [`DescriptorWallet::fetch_wallet`]. `Esplora`/`Electrum` sync via
+/// `bdk_esplora`'s/`bdk_electrum`'s blocking clients instead, for users
+/// running a pruned node or no local node at all -- see
+/// [`DescriptorWallet::fetch_wallet_esplora`]/[`DescriptorWallet::fetch_wallet_electrum`].
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainSource {
+    /// Sync via the node's bitcoind using `scanblocks` (the default today).
+    BitcoindScanBlocks,
+    /// Sync via an Esplora HTTP endpoint. `url` defaults to a known public
+    /// instance for the wallet's network (see `get_esplora_url`) when empty.
+    Esplora { url: String },
+    /// Sync via an Electrum server (`host:port`, optionally over SSL).
+    Electrum { url: String },
+}
+
+impl Default for ChainSource {
+    fn default() -> Self {
+        ChainSource::BitcoindScanBlocks
+    }
+}
+
+/// The plugin-wide data source, selected via `smaug_backend` and used as the
+/// [`ChainSource`] for any wallet that doesn't set its own `source`
+/// override. Unlike `ChainSource`, this also carries the bitcoind RPC
+/// connection details, since those are resolved once at startup rather than
+/// per-wallet.
+#[derive(Debug, Clone)]
+pub enum Backend {
+    BitcoindRpc { host: String, port: u16, auth: Auth },
+    Esplora { base_url: String },
+    Electrum { url: String },
+}
+
+impl Backend {
+    /// bitcoind RPC host to pass to [`DescriptorWallet::fetch_wallet`].
+    /// Meaningless (and unused) for non-bitcoind backends.
+    pub fn brpc_host(&self) -> String {
+        match self {
+            Backend::BitcoindRpc { host, .. } => host.clone(),
+            Backend::Esplora { .. } | Backend::Electrum { .. } => "127.0.0.1".to_owned(),
+        }
+    }
+
+    /// bitcoind RPC port; see [`Backend::brpc_host`].
+    pub fn brpc_port(&self) -> u16 {
+        match self {
+            Backend::BitcoindRpc { port, .. } => *port,
+            Backend::Esplora { .. } | Backend::Electrum { .. } => 8332,
+        }
+    }
+
+    /// bitcoind RPC auth; see [`Backend::brpc_host`].
+    pub fn brpc_auth(&self) -> Auth {
+        match self {
+            Backend::BitcoindRpc { auth, .. } => auth.clone(),
+            Backend::Esplora { .. } | Backend::Electrum { .. } => Auth::None,
+        }
+    }
+
+    /// The [`ChainSource`] a wallet without its own override should sync
+    /// through.
+    pub fn default_chain_source(&self) -> ChainSource {
+        match self {
+            Backend::BitcoindRpc { .. } => ChainSource::BitcoindScanBlocks,
+            Backend::Esplora { base_url } => ChainSource::Esplora {
+                url: base_url.clone(),
+            },
+            Backend::Electrum { url } => ChainSource::Electrum { url: url.clone() },
+        }
+    }
+}
+
 pub fn get_esplora_url(network: &str) -> String {
     match network {
         "bitcoin" | "mainnet" => "https://blockstream.info/api".to_owned(),
@@ -112,11 +246,184 @@ fn parse_currency(network: &Option<String>) -> Result<String, Error> {
     Ok(get_currency(parse_network(network)?))
 }
 
+/// Parses a `source` param into a [`ChainSource`]: `"bitcoind"`/`"core"` for
+/// the default node-backed sync, `"esplora:<url>"` to sync this wallet
+/// against an Esplora HTTP endpoint instead (`<url>` may be empty to fall
+/// back to [`get_esplora_url`]), or `"electrum:<url>"` to sync against an
+/// Electrum server.
+fn parse_chain_source(source: &str) -> Result<ChainSource, WatchError> {
+    let parsed = match source.split_once(':') {
+        Some(("esplora", url)) => ChainSource::Esplora { url: url.to_owned() },
+        Some(("electrum", url)) => ChainSource::Electrum { url: url.to_owned() },
+        _ if source == "bitcoind" || source == "core" => ChainSource::BitcoindScanBlocks,
+        _ => {
+            return Err(WatchError::InvalidFormat(format!(
+                "invalid source '{source}': expected 'bitcoind', 'esplora:<url>', or 'electrum:<url>'"
+            )))
+        }
+    };
+    Ok(parsed)
+}
+
 fn find_closest_lower_key(map: &BTreeMap<u32, BlockHash>, key: u32) -> Option<(u32, BlockHash)> {
     let mut iter = map.range(..key);
     iter.next_back().map(|(&k, v)| (k, v.clone()))
 }
 
+/// Resolves the height of every hash in `hashes` in a single JSON-RPC batch
+/// request (`getblockheader`, verbose), instead of one `getblockheader` round
+/// trip per block. `bitcoincore_rpc::Client`'s typed `RpcApi` methods don't
+/// expose batching, so this drops down to the `jsonrpc` client it's built on.
+fn batch_get_block_heights(
+    host: &str,
+    port: u16,
+    auth: &Auth,
+    hashes: &[BlockHash],
+) -> Result<BTreeMap<BlockHash, u32>, Error> {
+    if hashes.is_empty() {
+        return Ok(BTreeMap::new());
+    }
+
+    let (user, pass) = auth.clone().get_user_pass()?;
+    let transport = jsonrpc::simple_http::Builder::new()
+        .url(&format!("http://{host}:{port}"))
+        .map_err(|e| anyhow!("invalid bitcoind RPC url: {e}"))?
+        .auth(user.unwrap_or_default(), pass)
+        .build();
+    let client = jsonrpc::client::Client::with_transport(transport);
+
+    let raw_params: Vec<[Box<serde_json::value::RawValue>; 2]> = hashes
+        .iter()
+        .map(|hash| -> Result<_, Error> {
+            Ok([
+                serde_json::value::to_raw_value(&hash.to_string())?,
+                serde_json::value::to_raw_value(&true)?,
+            ])
+        })
+        .collect::<Result<_, Error>>()?;
+    let requests: Vec<jsonrpc::Request> = raw_params
+        .iter()
+        .map(|params| client.build_request("getblockheader", params))
+        .collect();
+
+    let responses = client
+        .send_batch(&requests)
+        .map_err(|e| anyhow!("batched getblockheader failed: {e}"))?;
+
+    let mut heights = BTreeMap::new();
+    for (hash, response) in hashes.iter().zip(responses) {
+        let response = response
+            .ok_or_else(|| anyhow!("no response for getblockheader({hash}) in batch"))?;
+        let info: bitcoincore_rpc::bitcoincore_rpc_json::GetBlockHeaderResult = response
+            .result()
+            .map_err(|e| anyhow!("getblockheader({hash}) batch response error: {e}"))?;
+        heights.insert(*hash, info.height.try_into().unwrap());
+    }
+    Ok(heights)
+}
+
+/// Adds `amount_fiat`/`fiat_currency` fields to a coin-movement notification
+/// object, if `rate` is available. Silently leaves `obj` untouched when
+/// `rate` is `None` (oracle unreachable or unconfigured) or the sat-to-fiat
+/// conversion overflows, so a pricing hiccup never blocks the notification
+/// itself.
+fn attach_fiat_fields(obj: &mut serde_json::Value, sats: u64, rate: Option<&Rate>) {
+    let Some(rate) = rate else {
+        return;
+    };
+    match sats_to_fiat(sats, rate) {
+        Ok(amount_fiat) => {
+            obj["amount_fiat"] = json!(amount_fiat.to_string());
+            obj["fiat_currency"] = json!(rate.currency);
+        }
+        Err(e) => {
+            log::warn!("skipping fiat fields for notification: {}", e);
+        }
+    }
+}
+
+/// Publishes `payload` (a `utxo_deposit`/`utxo_spent` notification object, in
+/// the same shape sent as a CLN custom notification) to the plugin-wide
+/// event-stream broadcast channel consumed by `smaug_event_listen`
+/// subscribers. A no-op when no one is currently listening.
+///
+/// Takes the `Sender` directly rather than locking `plugin.state()` itself:
+/// callers clone it out of `Smaug::event_tx` up front, so publishing an
+/// event never waits on the `State` mutex.
+fn broadcast_event(event_tx: &broadcast::Sender<serde_json::Value>, payload: serde_json::Value) {
+    let _ = event_tx.send(payload);
+}
+
+/// Looks up the keychain derivation index a given script pubkey was revealed
+/// at, so notifications can tell a bookkeeper exactly which address (parent
+/// descriptor + index) an output belongs to, not just which descriptor.
+fn derivation_index_of_script(
+    wallet: &Wallet<Store<'_, bdk::wallet::ChangeSet>>,
+    script: &bdk::bitcoin::Script,
+) -> Option<u32> {
+    wallet
+        .spk_index()
+        .index_of_spk(script.clone())
+        .map(|(_, index)| index)
+}
+
+/// Expands a single multipath descriptor (one containing a `<a;b>` step,
+/// e.g. `.../<0;1>/*`) into its external and internal descriptor strings.
+///
+/// Returns `Ok(None)` if `descriptor` contains no multipath group at all, so
+/// callers can fall back to treating it as a plain single-keychain
+/// descriptor.
+fn expand_multipath_descriptor(descriptor: &str) -> Result<Option<(String, String)>, WatchError> {
+    let start = match descriptor.find('<') {
+        Some(s) => s,
+        None => return Ok(None),
+    };
+    let end = descriptor[start..].find('>').map(|e| start + e).ok_or_else(|| {
+        WatchError::InvalidDescriptor(format!(
+            "unterminated multipath group in descriptor: {descriptor}"
+        ))
+    })?;
+    let inner = &descriptor[start + 1..end];
+    let branches: Vec<&str> = inner.split(';').collect();
+    if branches.len() != 2 {
+        return Err(WatchError::InvalidDescriptor(format!(
+            "multipath group must contain exactly two `;`-separated entries, found {} in <{}>",
+            branches.len(),
+            inner
+        )));
+    }
+    if descriptor[end + 1..].contains('<') {
+        return Err(WatchError::InvalidDescriptor(format!(
+            "descriptor must contain exactly one multipath group, found more than one: {descriptor}"
+        )));
+    }
+    let external = format!(
+        "{}{}{}",
+        &descriptor[..start],
+        branches[0],
+        &descriptor[end + 1..]
+    );
+    let internal = format!(
+        "{}{}{}",
+        &descriptor[..start],
+        branches[1],
+        &descriptor[end + 1..]
+    );
+    Ok(Some((external, internal)))
+}
+
+/// Derives a change descriptor from a BDK wallet-export's single external
+/// descriptor by rewriting its final `/0/*` derivation step to `/1/*`, the
+/// convention used instead of exporting a second descriptor. Returns `None`
+/// if no such segment is present.
+fn derive_change_descriptor(descriptor: &str) -> Option<String> {
+    descriptor.rfind("/0/*").map(|idx| {
+        let mut out = descriptor.to_owned();
+        out.replace_range(idx..idx + 4, "/1/*");
+        out
+    })
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, Parser)]
 #[command(author, version, about, long_about = None)]
 pub struct AddArgs {
@@ -130,6 +437,19 @@ pub struct AddArgs {
     pub gap: Option<u32>,
 }
 
+/// A single output belonging to a watched descriptor, tracked from the
+/// moment it's first seen until (and after) it's spent.
+///
+/// Spent outputs are kept around rather than removed so that historical
+/// reporting to bookkeeper stays consistent and re-syncing the same blocks
+/// twice doesn't change the reported picture.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Utxo {
+    pub value: u64,
+    pub spent: bool,
+    pub spending_txid: Option<Txid>,
+}
+
 /// Parameters related to the `smaug` command.
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct DescriptorWallet {
@@ -140,7 +460,26 @@ pub struct DescriptorWallet {
     pub last_synced: Option<u32>,
     // #[serde(skip_serializing, skip_deserializing)]
     pub transactions: BTreeMap<Txid, Transaction>,
+    /// Outputs belonging to this wallet, keyed by outpoint string (e.g.
+    /// `"<txid>:<vout>"`), spent or not.
+    #[serde(default)]
+    pub utxos: BTreeMap<String, Utxo>,
     pub network: Option<String>,
+    /// Which chain data source to sync this wallet from. Defaults to the
+    /// node's own bitcoind when absent.
+    #[serde(default)]
+    pub chain_source: ChainSource,
+    /// Unix timestamp of the last time we attempted a sync for this wallet,
+    /// used to honor `smaug_min_resync_interval`.
+    #[serde(default)]
+    pub last_sync_attempt_unix: Option<u64>,
+    /// Confirmation height last notified for every confirmed tx we've
+    /// already emitted deposit/spend/fee events for. Diffed against the
+    /// canonical set on every sync so a reorg that drops or moves a tx can
+    /// be compensated for with a [`REORG_TAG`] notification instead of
+    /// silently leaving the downstream ledger double-counted.
+    #[serde(default)]
+    pub notified_confirmations: BTreeMap<Txid, u32>,
 }
 impl DescriptorWallet {
     fn new(
@@ -149,8 +488,20 @@ impl DescriptorWallet {
         birthday: Option<u64>,
         gap: Option<u64>,
         network: Option<String>,
+        source: Option<ChainSource>,
     ) -> Result<Self, WatchError> {
-        let mut params = DescriptorWallet::from_descriptor(descriptor)?;
+        let mut params;
+        if change_descriptor.is_none() {
+            if let Some((external, internal)) = expand_multipath_descriptor(descriptor)? {
+                log::debug!("expanded multipath descriptor into external + change keychains");
+                params = DescriptorWallet::from_descriptor(&external)?;
+                params = params.with_change_descriptor(&internal)?;
+            } else {
+                params = DescriptorWallet::from_descriptor(descriptor)?;
+            }
+        } else {
+            params = DescriptorWallet::from_descriptor(descriptor)?;
+        }
         if change_descriptor.is_some() {
             params = params.with_change_descriptor(change_descriptor.unwrap())?
         }
@@ -163,6 +514,9 @@ impl DescriptorWallet {
         if network.is_some() {
             params = params.with_network(network.unwrap())?
         }
+        if let Some(source) = source {
+            params = params.with_chain_source(source)?
+        }
         Ok(params)
     }
 
@@ -173,8 +527,12 @@ impl DescriptorWallet {
             birthday: args.birthday,
             gap: args.gap,
             transactions: BTreeMap::new(),
+            utxos: BTreeMap::new(),
             network: Some(network),
             last_synced: None,
+            chain_source: ChainSource::default(),
+            last_sync_attempt_unix: None,
+            notified_confirmations: BTreeMap::new(),
         })
     }
 
@@ -185,8 +543,12 @@ impl DescriptorWallet {
             birthday: None,
             gap: None,
             transactions: BTreeMap::new(),
+            utxos: BTreeMap::new(),
             network: None,
             last_synced: None,
+            chain_source: ChainSource::default(),
+            last_sync_attempt_unix: None,
+            notified_confirmations: BTreeMap::new(),
         })
     }
 
@@ -236,6 +598,13 @@ impl DescriptorWallet {
         })
     }
 
+    pub fn with_chain_source(self, chain_source: ChainSource) -> Result<Self, WatchError> {
+        Ok(Self {
+            chain_source,
+            ..self
+        })
+    }
+
     fn sats_to_msats(amount: u64) -> u64 {
         amount * 1000
     }
@@ -259,6 +628,80 @@ impl DescriptorWallet {
         self.last_synced = Some(height);
     }
 
+    /// Whether at least `min_resync_interval_secs` have elapsed since the
+    /// last sync attempt for this wallet (or it has never been synced).
+    /// Lets the polling loop skip re-scanning a wallet it just refreshed.
+    pub fn should_sync_now(&self, min_resync_interval_secs: u64) -> bool {
+        if min_resync_interval_secs == 0 {
+            return true;
+        }
+        match self.last_sync_attempt_unix {
+            None => true,
+            Some(last) => {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(last);
+                now.saturating_sub(last) >= min_resync_interval_secs
+            }
+        }
+    }
+
+    /// Records that we just attempted (or are about to attempt) a sync, for
+    /// use by [`DescriptorWallet::should_sync_now`].
+    pub fn update_last_sync_attempt(&mut self) {
+        self.last_sync_attempt_unix = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()
+            .map(|d| d.as_secs());
+    }
+
+    /// Records the effect of `tx` on this wallet's UTXO set: any output we
+    /// own becomes a new unspent entry, and any input spending an outpoint we
+    /// already knew about marks that entry spent (it is never removed).
+    pub fn update_utxos(
+        &mut self,
+        wallet: &Wallet<Store<'_, bdk::wallet::ChangeSet>>,
+        tx: &CanonicalTx<'_, Transaction, ConfirmationTimeAnchor>,
+    ) {
+        for (vout, output) in tx.tx_node.tx.output.iter().enumerate() {
+            if wallet.is_mine(&output.script_pubkey) {
+                let outpoint = format!("{}:{}", tx.tx_node.txid, vout);
+                self.utxos.entry(outpoint).or_insert(Utxo {
+                    value: output.value,
+                    spent: false,
+                    spending_txid: None,
+                });
+            }
+        }
+        for input in tx.tx_node.tx.input.iter() {
+            let outpoint = input.previous_output.to_string();
+            if let Some(utxo) = self.utxos.get_mut(&outpoint) {
+                utxo.spent = true;
+                utxo.spending_txid = Some(tx.tx_node.txid);
+            }
+        }
+    }
+
+    /// Returns this wallet's tracked UTXOs, optionally including spent ones.
+    pub fn utxos(&self, include_spent: bool) -> Vec<(&String, &Utxo)> {
+        self.utxos
+            .iter()
+            .filter(|(_, u)| include_spent || !u.spent)
+            .collect()
+    }
+
+    /// Total unspent value, in satoshis, of the outputs recorded by
+    /// [`DescriptorWallet::update_utxos`]. Outputs of unconfirmed
+    /// transactions are counted too when the chain source reported them.
+    pub fn balance(&self) -> u64 {
+        self.utxos
+            .values()
+            .filter(|u| !u.spent)
+            .map(|u| u.value)
+            .sum()
+    }
+
     pub fn get_network(&self) -> Result<Network, Error> {
         parse_network(&self.network)
     }
@@ -275,32 +718,176 @@ impl DescriptorWallet {
         )?)
     }
 
-    pub async fn fetch_wallet<'a>(
-        &mut self,
-        db_dir: PathBuf,
-        brpc_host: String,
-        brpc_port: u16,
-        brpc_auth: Auth,
-    ) -> Result<Wallet<Store<'_, bdk::wallet::ChangeSet>>, Error> {
+    /// Opens (or creates) this wallet's on-disk `Store` and builds the BDK
+    /// `Wallet` over it, without talking to any chain source. Shared by
+    /// [`DescriptorWallet::fetch_wallet`] and
+    /// [`DescriptorWallet::sync_single_block`], which differ only in how
+    /// they bring that wallet up to date.
+    fn open_wallet<'a>(&self, db_dir: PathBuf) -> Result<Wallet<Store<'a, bdk::wallet::ChangeSet>>, Error> {
         log::trace!("creating path");
         let db_filename = self.get_name()?;
         let db_path = db_dir.join(format!("{}.db", db_filename,));
         log::trace!("searching for path: {:?}", db_path);
         let db = Store::<bdk::wallet::ChangeSet>::new_from_path(SMAUG_DATADIR.as_bytes(), db_path)?;
         log::trace!("db created!");
-        let external_descriptor = self.descriptor.clone();
-        let internal_descriptor = self.change_descriptor.clone();
-        let mut wallet = Wallet::new(
-            &external_descriptor,
-            internal_descriptor.as_ref(),
+        let wallet = Wallet::new(
+            &self.descriptor,
+            self.change_descriptor.as_ref(),
             db,
             self.get_network()?,
         )?;
         log::trace!("wallet created!");
+        Ok(wallet)
+    }
+
+    /// Whether `height` is exactly one block ahead of `self.last_synced` on
+    /// [`ChainSource::BitcoindScanBlocks`] -- i.e. the cheap single-block path
+    /// in [`DescriptorWallet::sync_single_block`] applies -- rather than this
+    /// wallet having fallen more than one block behind (e.g. after the plugin
+    /// was down) or a reorg having replaced the tip, either of which still
+    /// needs a full [`DescriptorWallet::fetch_wallet`] to re-derive the
+    /// canonical set and re-run reorg detection.
+    pub fn is_next_block(&self, height: u32) -> bool {
+        matches!(self.chain_source, ChainSource::BitcoindScanBlocks)
+            && self.last_synced.map_or(false, |ls| height == ls + 1)
+    }
+
+    /// If a keychain's last-used index landed within half of `lookahead`
+    /// of the current frontier, doubles that keychain's lookahead so syncing
+    /// keeps watching ahead of wherever usage actually is, rather than going
+    /// stale after a burst of activity near the edge of what we derived.
+    fn grow_lookahead_near_frontier(
+        wallet: &mut Wallet<Store<'_, bdk::wallet::ChangeSet>>,
+        lookahead: u32,
+    ) -> Result<(), Error> {
+        for keychain in [bdk::KeychainKind::External, bdk::KeychainKind::Internal] {
+            // `derivation_index` is the frontier: the last index we've
+            // actually derived (and thus cached) scripts up to. `last_used`
+            // is how far the wallet's usage has actually walked into that
+            // derived range. Grow only once usage gets within half a gap of
+            // the frontier, rather than comparing usage to the lookahead
+            // size directly (which fires on essentially every synced
+            // wallet).
+            let Some(frontier) = wallet.derivation_index(keychain) else {
+                continue;
+            };
+            let Some(last_used) = wallet.spk_index().last_used_index(keychain) else {
+                continue;
+            };
+            if frontier.saturating_sub(last_used) < lookahead / 2 {
+                let grown = lookahead * 2;
+                log::debug!(
+                    "{:?} keychain usage ({}) is near the lookahead frontier ({}); growing to {}",
+                    keychain,
+                    last_used,
+                    frontier,
+                    grown
+                );
+                wallet.set_lookahead(keychain, grown)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Opens this wallet's `Store`, fetches only `block_hash` (the block at
+    /// `height`, which must be `self.last_synced + 1` -- see
+    /// [`DescriptorWallet::is_next_block`]) via bitcoind RPC, and applies it
+    /// to the wallet's tx graph relative to its current chain tip, advancing
+    /// `self.last_synced` to `height`. Returns the resulting wallet alongside
+    /// the txids this block introduced, so callers can filter
+    /// `wallet.transactions()` down to this block's delta instead of
+    /// re-diffing the whole history.
+    ///
+    /// `height == self.last_synced + 1` alone doesn't rule out a reorg: if
+    /// the plugin missed one while down (or between `block_added`
+    /// notifications), the next live block can satisfy that arithmetic while
+    /// the block at `self.last_synced` itself changed underneath us. So
+    /// before applying, this checks the fetched block's `prev_blockhash`
+    /// against our current chain tip's hash; on a mismatch it returns `Ok(None)`
+    /// without touching `wallet` or `self.last_synced`, and the caller
+    /// (`block_added_handler`) falls back to a full [`DescriptorWallet::fetch_wallet`]
+    /// rescan -- which, unlike this fast path, runs reorg detection via
+    /// `notify_reorgs` -- instead of silently applying a block on top of a
+    /// stale tip.
+    pub async fn sync_single_block(
+        &mut self,
+        db_dir: PathBuf,
+        brpc_host: String,
+        brpc_port: u16,
+        brpc_auth: Auth,
+        height: u32,
+        block_hash: BlockHash,
+    ) -> Result<Option<(Wallet<Store<'_, bdk::wallet::ChangeSet>>, HashSet<Txid>)>, Error> {
+        let mut wallet = self.open_wallet(db_dir)?;
+        let mut rpc_client = build_rpc_client(&brpc_host, brpc_port, &brpc_auth)?;
+
+        let block = match rpc_client.get_block(&block_hash) {
+            Ok(block) => block,
+            Err(e) if is_auth_error(&e) => {
+                log::warn!(
+                    "getblock auth failed ({}); re-reading bitcoind credentials and retrying",
+                    e
+                );
+                rpc_client = build_rpc_client(&brpc_host, brpc_port, &brpc_auth)?;
+                rpc_client.get_block(&block_hash)?
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let prev_block_id = wallet.latest_checkpoint().map(|cp| cp.block_id());
+        match prev_block_id {
+            Some(pbi) if block.header.prev_blockhash == pbi.hash => {}
+            _ => {
+                log::warn!(
+                    "block {} at height {} doesn't chain onto our current tip; falling back to a full rescan to re-run reorg detection",
+                    block_hash, height
+                );
+                return Ok(None);
+            }
+        }
+        let block_txids: HashSet<Txid> = block.txdata.iter().map(|tx| tx.txid()).collect();
+
+        wallet.apply_block_relevant(block, prev_block_id, height)?;
+        wallet.commit()?;
+        self.update_last_synced(height);
+
+        // `apply_block_relevant` only recognizes an output as ours if it
+        // falls within the keychain's already-derived lookahead window;
+        // growing it here (same as `fetch_wallet`'s post-scan check) keeps a
+        // run of per-block applies from going stale the way `fetch_wallet`'s
+        // growth check would if this wallet never took the full-scan path.
+        let lookahead = self.gap.unwrap_or(DEFAULT_LOOKAHEAD);
+        Self::grow_lookahead_near_frontier(&mut wallet, lookahead)?;
+
+        Ok(Some((wallet, block_txids)))
+    }
+
+    /// Syncs and returns this wallet, dispatching on `self.chain_source` so
+    /// callers (`spend_tx_notify`/`receive_tx_notify` and friends) get back
+    /// an identically-shaped `Wallet` regardless of which backend did the
+    /// syncing. [`ChainSource::BitcoindScanBlocks`] is handled inline below;
+    /// `Esplora`/`Electrum` hand off to their `fetch_wallet_*` sibling.
+    pub async fn fetch_wallet<'a>(
+        &mut self,
+        db_dir: PathBuf,
+        brpc_host: String,
+        brpc_port: u16,
+        brpc_auth: Auth,
+    ) -> Result<Wallet<Store<'_, bdk::wallet::ChangeSet>>, Error> {
+        let external_descriptor = self.descriptor.clone();
+        let internal_descriptor = self.change_descriptor.clone();
+        let mut wallet = self.open_wallet(db_dir)?;
 
         let balance = wallet.get_balance();
         log::trace!("Wallet balance before syncing: {} sats", balance.total());
 
+        match self.chain_source.clone() {
+            ChainSource::BitcoindScanBlocks => {}
+            ChainSource::Esplora { url } => return self.fetch_wallet_esplora(wallet, url).await,
+            ChainSource::Electrum { url } => {
+                return self.fetch_wallet_electrum(wallet, url).await
+            }
+        }
+
         log::trace!("Syncing...");
         log::debug!("using network: {}", json!(self.network).as_str().unwrap());
 
@@ -310,19 +897,71 @@ impl DescriptorWallet {
             format!("http://{}:{}", brpc_host.clone(), brpc_port.clone())
         );
 
-        let rpc_client = Client::new_with_timeout(
-            &format!("http://{}:{}", brpc_host.clone(), brpc_port.clone()),
-            brpc_auth,
-            // Auth::UserPass(brpc_user.clone(), brpc_pass.clone()), // Auth::CookieFile(PathBuf::from("/home/cguida/.bitcoin/regtest/.cookie"))
-            Duration::from_secs(3600),
-        )?;
+        let mut rpc_client = build_rpc_client(&brpc_host, brpc_port, &brpc_auth)?;
 
-        let external_descriptor = ScanBlocksRequestDescriptor::Extended {
-            desc: external_descriptor.to_string(),
-            range: None,
+        // A wallet with no prior sync is either brand new or was just
+        // imported: run the stop-gap recovery scan so funds beyond the
+        // first lookahead window (e.g. a restored wallet that was used
+        // extensively) aren't missed. Otherwise do a single incremental
+        // pass at the wallet's configured gap.
+        let lookahead = if self.last_synced.is_none() {
+            self.recovery_scan(
+                &mut wallet,
+                &mut rpc_client,
+                &brpc_host,
+                brpc_port,
+                &brpc_auth,
+                &external_descriptor,
+                internal_descriptor.as_deref(),
+            )
+            .await?
+        } else {
+            let lookahead = self.gap.unwrap_or(DEFAULT_LOOKAHEAD);
+            self.scan_with_lookahead(
+                &mut wallet,
+                &mut rpc_client,
+                &brpc_host,
+                brpc_port,
+                &brpc_auth,
+                &external_descriptor,
+                internal_descriptor.as_deref(),
+                lookahead,
+            )
+            .await?;
+            lookahead
         };
-        let mut descriptors_vec = vec![external_descriptor];
 
+        Self::grow_lookahead_near_frontier(&mut wallet, lookahead)?;
+
+        log::debug!("last_synced after scan = {:?}", self.last_synced);
+
+        let balance = wallet.get_balance();
+        log::trace!("Wallet balance after syncing: {} sats", balance.total());
+        return Ok(wallet);
+    }
+
+    /// Runs a single `scanblocks` pass with `lookahead` applied to both
+    /// keychains, starting from `self.last_synced` (or from the start of the
+    /// chain if `None`), and applies every newly-relevant block to
+    /// `wallet`. Updates `self.last_synced` to the scan's chain tip and
+    /// returns whether the pass touched any blocks at all.
+    async fn scan_with_lookahead(
+        &mut self,
+        wallet: &mut Wallet<Store<'_, bdk::wallet::ChangeSet>>,
+        rpc_client: &mut Client,
+        brpc_host: &str,
+        brpc_port: u16,
+        brpc_auth: &Auth,
+        external_descriptor: &str,
+        internal_descriptor: Option<&str>,
+        lookahead: u32,
+    ) -> Result<bool, Error> {
+        wallet.set_lookahead_for_all(lookahead)?;
+
+        let mut descriptors_vec = vec![ScanBlocksRequestDescriptor::Extended {
+            desc: external_descriptor.to_string(),
+            range: None,
+        }];
         if let Some(id) = internal_descriptor {
             descriptors_vec.push(ScanBlocksRequestDescriptor::Extended {
                 desc: id.to_string(),
@@ -330,8 +969,6 @@ impl DescriptorWallet {
             });
         }
 
-        wallet.set_lookahead_for_all(20)?;
-
         log::info!("last_synced = {:?}", self.last_synced);
         let start_height: Option<u64> = match self.last_synced {
             Some(ct) => Some(ct.into()),
@@ -339,7 +976,7 @@ impl DescriptorWallet {
         };
 
         let descriptors = &descriptors_vec[..];
-        let request = ScanBlocksRequest {
+        let build_request = || ScanBlocksRequest {
             scanobjects: descriptors,
             start_height,
             stop_height: None,
@@ -348,10 +985,23 @@ impl DescriptorWallet {
                 filter_false_positives: Some(true),
             }),
         };
-        let res: ScanBlocksResult = rpc_client.scan_blocks_blocking(request)?;
+        let res: ScanBlocksResult = match rpc_client.scan_blocks_blocking(build_request()) {
+            Ok(res) => res,
+            Err(e) if is_auth_error(&e) => {
+                log::warn!(
+                    "scanblocks auth failed ({}); re-reading bitcoind credentials and retrying",
+                    e
+                );
+                *rpc_client = build_rpc_client(brpc_host, brpc_port, brpc_auth)?;
+                rpc_client.scan_blocks_blocking(build_request())?
+            }
+            Err(e) => return Err(e.into()),
+        };
         log::trace!("scanblocks result: {:?}", res);
         log::trace!("wallet = {:?}", wallet);
 
+        let found_activity = !res.relevant_blocks.is_empty();
+
         let chain_tip = wallet.latest_checkpoint();
         let mut prev_block_id = match chain_tip {
             Some(ct) => Some(ct.block_id()),
@@ -360,17 +1010,44 @@ impl DescriptorWallet {
 
         // prev_block_id needs to be the block immediately before our current block
 
+        // Resolve height-per-block up front as real JSON-RPC batches of
+        // `HEIGHT_BATCH_SIZE`, instead of interleaving a `get_block_header_info`
+        // call with every single `get_block` call below (or even issuing one
+        // `get_block_header_info` round trip per block): each chunk is a
+        // single HTTP round trip covering up to `HEIGHT_BATCH_SIZE` headers.
+        const HEIGHT_BATCH_SIZE: usize = 100;
+        let mut height_by_hash: BTreeMap<BlockHash, u32> = BTreeMap::new();
+        for chunk in res.relevant_blocks.chunks(HEIGHT_BATCH_SIZE) {
+            let batch = match batch_get_block_heights(brpc_host, brpc_port, brpc_auth, chunk) {
+                Ok(batch) => batch,
+                Err(e) => {
+                    // `Auth::CookieFile` re-reads the cookie's contents each
+                    // call rather than caching them, so simply retrying picks
+                    // up credentials bitcoind may have rotated on restart.
+                    log::warn!(
+                        "batched getblockheader failed ({}); retrying once in case credentials rotated",
+                        e
+                    );
+                    batch_get_block_heights(brpc_host, brpc_port, brpc_auth, chunk)?
+                }
+            };
+            height_by_hash.extend(batch);
+        }
+
         for bh in res.relevant_blocks {
-            let block = rpc_client.get_block(&bh)?;
-            // let height: u32 = block.bip34_block_height()?.try_into().unwrap();
-            // we really should not have to make two separate RPC calls here.
-            // unfortunately rust-bitcoin does not expose an rpc method that returns
-            // both the full transaction dump and the height.
-            let height: u32 = rpc_client
-                .get_block_header_info(&bh)?
-                .height
-                .try_into()
-                .unwrap();
+            let block = match rpc_client.get_block(&bh) {
+                Ok(block) => block,
+                Err(e) if is_auth_error(&e) => {
+                    log::warn!(
+                        "getblock auth failed ({}); re-reading bitcoind credentials and retrying",
+                        e
+                    );
+                    *rpc_client = build_rpc_client(brpc_host, brpc_port, brpc_auth)?;
+                    rpc_client.get_block(&bh)?
+                }
+                Err(e) => return Err(e.into()),
+            };
+            let height = *height_by_hash.get(&bh).expect("height was resolved above");
             if let Some(p) = prev_block_id {
                 if height <= p.height {
                     if let Some((height, hash)) =
@@ -389,21 +1066,235 @@ impl DescriptorWallet {
 
         self.update_last_synced(res.to_height.try_into().unwrap());
 
-        log::debug!("last_synced after scan = {:?}", self.last_synced);
+        Ok(found_activity)
+    }
 
-        let balance = wallet.get_balance();
-        log::trace!("Wallet balance after syncing: {} sats", balance.total());
-        return Ok(wallet);
+    /// Stop-gap recovery scan for a freshly imported/rescanned descriptor.
+    ///
+    /// Starts at `self.gap` (or [`DEFAULT_LOOKAHEAD`]) addresses on both
+    /// keychains and keeps rescanning from scratch with the lookahead grown
+    /// by that same amount as long as the previous pass turned up a used
+    /// address past the ones the pass before it had found, stopping once a
+    /// pass finds no activity or no newly used address on either keychain
+    /// -- i.e. the `gap` addresses added last were all unused on both the
+    /// external and internal descriptor. Returns the final lookahead applied.
+    async fn recovery_scan(
+        &mut self,
+        wallet: &mut Wallet<Store<'_, bdk::wallet::ChangeSet>>,
+        rpc_client: &mut Client,
+        brpc_host: &str,
+        brpc_port: u16,
+        brpc_auth: &Auth,
+        external_descriptor: &str,
+        internal_descriptor: Option<&str>,
+    ) -> Result<u32, Error> {
+        let gap = self.gap.unwrap_or(DEFAULT_LOOKAHEAD);
+        let mut lookahead = gap;
+        let mut prev_last_used = None;
+        loop {
+            log::debug!("recovery scan: scanning with lookahead {}", lookahead);
+            // Re-scan from the wallet's birthday each pass: a bigger
+            // lookahead derives addresses a previous, narrower pass never
+            // looked at, so we can't just pick up from the old chain tip.
+            self.last_synced = self.birthday;
+            let found_activity = self
+                .scan_with_lookahead(
+                    wallet,
+                    rpc_client,
+                    brpc_host,
+                    brpc_port,
+                    brpc_auth,
+                    external_descriptor,
+                    internal_descriptor,
+                    lookahead,
+                )
+                .await?;
+            if !found_activity {
+                log::debug!(
+                    "recovery scan: no activity with lookahead {}, stopping",
+                    lookahead
+                );
+                break;
+            }
+            // Every pass re-reports the blocks earlier passes already found,
+            // so stop once widening the lookahead found no newly used address.
+            let last_used = (
+                wallet.spk_index().last_used_index(bdk::KeychainKind::External),
+                wallet.spk_index().last_used_index(bdk::KeychainKind::Internal),
+            );
+            if prev_last_used == Some(last_used) {
+                log::debug!(
+                    "recovery scan: no newly used address with lookahead {}, stopping",
+                    lookahead
+                );
+                break;
+            }
+            prev_last_used = Some(last_used);
+            lookahead += gap;
+        }
+        Ok(lookahead)
+    }
+
+    /// Number of SPK/header requests `fetch_wallet_esplora` keeps in flight at
+    /// once against the Esplora server, mirroring how the bitcoind path
+    /// batches its own `getblockheader` round trips.
+    const ESPLORA_PARALLEL_REQUESTS: usize = 5;
+
+    /// Number of script/header requests `fetch_wallet_electrum` batches into
+    /// a single round trip against the Electrum server; see
+    /// [`Self::ESPLORA_PARALLEL_REQUESTS`] for the Esplora-side equivalent.
+    const ELECTRUM_BATCH_SIZE: usize = 5;
+
+    /// Syncs `wallet` against an Esplora HTTP endpoint instead of bitcoind.
+    ///
+    /// This is the entry point `fetch_wallet` hands off to when
+    /// `chain_source` is [`ChainSource::Esplora`]. Mirrors the bitcoind path:
+    /// a wallet with no prior sync runs a full scan of both keychains up to
+    /// `self.gap`/[`DEFAULT_LOOKAHEAD`] addresses past the last active index
+    /// (BDK's own stop-gap semantics, equivalent to the bitcoind path's
+    /// `recovery_scan`); the resulting tx graph and chain update are applied
+    /// and committed the same way [`DescriptorWallet::scan_with_lookahead`]
+    /// does, so downstream (`spend_tx_notify`/`receive_tx_notify`) sees an
+    /// identically-shaped `Wallet` regardless of backend.
+    async fn fetch_wallet_esplora<'a>(
+        &mut self,
+        mut wallet: Wallet<Store<'a, bdk::wallet::ChangeSet>>,
+        url: String,
+    ) -> Result<Wallet<Store<'a, bdk::wallet::ChangeSet>>, Error> {
+        let url = if url.is_empty() {
+            get_esplora_url(self.network.as_deref().unwrap_or("bitcoin"))
+        } else {
+            url
+        };
+        let client = esplora_client::Builder::new(&url).build_blocking();
+        let stop_gap = self.gap.unwrap_or(DEFAULT_LOOKAHEAD) as usize;
+
+        let prev_tip = wallet.latest_checkpoint();
+        let keychain_spks = wallet.all_unbounded_spk_iters();
+        let (graph_update, last_active_indices) = client
+            .full_scan(keychain_spks, stop_gap, Self::ESPLORA_PARALLEL_REQUESTS)
+            .map_err(|e| anyhow!("esplora full_scan against {} failed: {}", url, e))?;
+
+        let missing_heights = graph_update.missing_heights(wallet.local_chain());
+        let chain_update = client
+            .update_local_chain(prev_tip, missing_heights)
+            .map_err(|e| anyhow!("esplora chain update against {} failed: {}", url, e))?;
+
+        wallet.apply_update(bdk::wallet::Update {
+            last_active_indices,
+            graph: graph_update,
+            chain: Some(chain_update),
+        })?;
+        wallet.commit()?;
+
+        if let Some(tip) = wallet.latest_checkpoint() {
+            self.update_last_synced(tip.block_id().height);
+        }
+        Self::grow_lookahead_near_frontier(&mut wallet, stop_gap as u32)?;
+
+        Ok(wallet)
+    }
+
+    /// Syncs `wallet` against an Electrum server instead of bitcoind.
+    ///
+    /// Mirrors [`DescriptorWallet::fetch_wallet_esplora`]'s shape: a
+    /// `full_scan` out to `self.gap`/[`DEFAULT_LOOKAHEAD`] past the last
+    /// active index, followed by a local-chain update against the server's
+    /// tip, applied and committed the same way so downstream
+    /// (`spend_tx_notify`/`receive_tx_notify`) sees an identically-shaped
+    /// `Wallet` regardless of backend.
+    async fn fetch_wallet_electrum<'a>(
+        &mut self,
+        mut wallet: Wallet<Store<'a, bdk::wallet::ChangeSet>>,
+        url: String,
+    ) -> Result<Wallet<Store<'a, bdk::wallet::ChangeSet>>, Error> {
+        let client = electrum_client::Client::new(&url)
+            .map_err(|e| anyhow!("connecting to electrum server {} failed: {}", url, e))?;
+        let stop_gap = self.gap.unwrap_or(DEFAULT_LOOKAHEAD) as usize;
+
+        let prev_tip = wallet.latest_checkpoint();
+        let keychain_spks = wallet.all_unbounded_spk_iters();
+        let (graph_update, last_active_indices) = client
+            .full_scan(keychain_spks, stop_gap, Self::ELECTRUM_BATCH_SIZE)
+            .map_err(|e| anyhow!("electrum full_scan against {} failed: {}", url, e))?;
+
+        let missing_heights = graph_update.missing_heights(wallet.local_chain());
+        let chain_update = client
+            .update_local_chain(prev_tip, missing_heights)
+            .map_err(|e| anyhow!("electrum chain update against {} failed: {}", url, e))?;
+
+        wallet.apply_update(bdk::wallet::Update {
+            last_active_indices,
+            graph: graph_update,
+            chain: Some(chain_update),
+        })?;
+        wallet.commit()?;
+
+        if let Some(tip) = wallet.latest_checkpoint() {
+            self.update_last_synced(tip.block_id().height);
+        }
+        Self::grow_lookahead_near_frontier(&mut wallet, stop_gap as u32)?;
+
+        Ok(wallet)
     }
 
     // assume we own all inputs, ie sent from our wallet. all inputs and outputs should generate coin movement bookkeeper events
     async fn spend_tx_notify<'a>(
         &self,
         plugin: &Plugin<State>,
+        event_tx: &broadcast::Sender<serde_json::Value>,
         wallet: &Wallet<Store<'_, bdk::wallet::ChangeSet>>,
         tx: &CanonicalTx<'_, Transaction, ConfirmationTimeAnchor>,
+        fiat_oracle: &mut PriceOracle,
     ) -> Result<(), Error> {
         let coin_type = parse_currency(&self.network)?;
+        let rate = match tx.chain_position {
+            ChainPosition::Unconfirmed(_) => None,
+            ChainPosition::Confirmed(a) => fiat_oracle.rate_at(a.confirmation_height),
+        };
+
+        // The miner fee never shows up in the per-input/output deposit and
+        // spend events below, so a bookkeeper ledger built from just those
+        // wouldn't balance for a self-spend. Emit it as its own event.
+        if let ChainPosition::Confirmed(a) = tx.chain_position {
+            let inputs_total = tx.tx_node.tx.input.iter().try_fold(0u64, |acc, input| {
+                wallet
+                    .tx_graph()
+                    .get_txout(input.previous_output)
+                    .map(|po| acc + po.value)
+            });
+            match inputs_total {
+                Some(inputs_total) => {
+                    let outputs_total: u64 = tx.tx_node.tx.output.iter().map(|o| o.value).sum();
+                    let fee = inputs_total.saturating_sub(outputs_total);
+                    let acct = format!("smaug:{}", self.get_name()?);
+                    let onchain_fee = json!({ONCHAIN_FEE_TAG: {
+                        "account": acct,
+                        "txid": tx.tx_node.txid,
+                        "fee_msat": Self::sats_to_msats(fee),
+                        "coin_type": coin_type,
+                        "timestamp": format!("{}", a.confirmation_time),
+                        "blockheight": format!("{}", a.confirmation_height),
+                    }});
+                    let cloned_plugin = plugin.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = cloned_plugin
+                            .send_custom_notification(ONCHAIN_FEE_TAG.to_string(), onchain_fee)
+                            .await
+                        {
+                            log::error!("Error sending custom notification: {:?}", e);
+                        }
+                    });
+                }
+                None => {
+                    log::warn!(
+                        "skipping onchain_fee notification for txid {}: a prevout is missing from the tx graph",
+                        tx.tx_node.txid
+                    );
+                }
+            }
+        }
+
         // send spent notification for each input
         for input in tx.tx_node.tx.input.iter() {
             if let Some(po) = wallet.tx_graph().get_txout(input.previous_output) {
@@ -416,16 +1307,21 @@ impl DescriptorWallet {
                         let amount = po.value;
                         let outpoint = format!("{}", input.previous_output.to_string());
                         log::trace!("outpoint = {}", format!("{}", outpoint));
-                        let onchain_spend = json!({UTXO_SPENT_TAG: {
+                        let mut onchain_spend = json!({UTXO_SPENT_TAG: {
                             "account": acct,
                             "outpoint": outpoint,
                             "spending_txid": tx.tx_node.txid,
                             "amount_msat": Self::sats_to_msats(amount),
+                            "parent_descriptor": self.descriptor.clone(),
+                            "parent_descriptor_checksum": self.get_name()?,
+                            "derivation_index": derivation_index_of_script(wallet, &po.script_pubkey),
                             "coin_type": coin_type,
                             "timestamp": format!("{}", a.confirmation_time),
                             "blockheight": format!("{}", a.confirmation_height),
                         }});
+                        attach_fiat_fields(&mut onchain_spend[UTXO_SPENT_TAG], amount, rate.as_ref());
                         log::trace!("INSIDE SEND SPEND NOTIFICATION ON SMAUG SIDE");
+                        broadcast_event(event_tx, onchain_spend.clone());
                         let cloned_plugin = plugin.clone();
                         tokio::spawn(async move {
                             if let Err(e) = cloned_plugin
@@ -462,17 +1358,22 @@ impl DescriptorWallet {
                     let amount = output.value;
                     let outpoint = format!("{}:{}", tx.tx_node.txid.to_string(), vout.to_string());
                     log::trace!("outpoint = {}", format!("{}:{}", tx.tx_node.txid, vout));
-                    let onchain_deposit = json!({UTXO_DEPOSIT_TAG:{
+                    let mut onchain_deposit = json!({UTXO_DEPOSIT_TAG:{
                             "account": acct,
                             "transfer_from": transfer_from,
                             "outpoint": outpoint,
                             "spending_txid": tx.tx_node.txid,
                             "amount_msat": Self::sats_to_msats(amount),
+                            "parent_descriptor": self.descriptor.clone(),
+                            "parent_descriptor_checksum": self.get_name()?,
+                            "derivation_index": derivation_index_of_script(wallet, &output.script_pubkey),
                             "coin_type": coin_type,
                             "timestamp": format!("{}", a.confirmation_time),
                             "blockheight": format!("{}", a.confirmation_height),
                     }});
+                    attach_fiat_fields(&mut onchain_deposit[UTXO_DEPOSIT_TAG], amount, rate.as_ref());
                     log::trace!("INSIDE SEND DEPOSIT NOTIFICATION ON SMAUG SIDE");
+                    broadcast_event(event_tx, onchain_deposit.clone());
                     let cloned_plugin = plugin.clone();
                     tokio::spawn(async move {
                         if let Err(e) = cloned_plugin
@@ -494,10 +1395,16 @@ impl DescriptorWallet {
     async fn receive_tx_notify<'a>(
         &self,
         plugin: &Plugin<State>,
+        event_tx: &broadcast::Sender<serde_json::Value>,
         wallet: &Wallet<Store<'_, bdk::wallet::ChangeSet>>,
         tx: &CanonicalTx<'_, Transaction, ConfirmationTimeAnchor>,
+        fiat_oracle: &mut PriceOracle,
     ) -> Result<(), Error> {
         let coin_type = parse_currency(&self.network)?;
+        let rate = match tx.chain_position {
+            ChainPosition::Unconfirmed(_) => None,
+            ChainPosition::Confirmed(a) => fiat_oracle.rate_at(a.confirmation_height),
+        };
         for (vout, output) in tx.tx_node.tx.output.iter().enumerate() {
             if wallet.is_mine(&output.script_pubkey) {
                 match tx.chain_position {
@@ -519,17 +1426,22 @@ impl DescriptorWallet {
                             "outpoint = {}",
                             format!("{}:{}", tx.tx_node.txid.to_string(), vout.to_string())
                         );
-                        let onchain_deposit = json!({UTXO_DEPOSIT_TAG: {
+                        let mut onchain_deposit = json!({UTXO_DEPOSIT_TAG: {
                                 "account": acct,
                                 "transfer_from": transfer_from,
                                 "outpoint": outpoint,
                                 "spending_txid": tx.tx_node.txid.to_string(),
                                 "amount_msat": Self::sats_to_msats(amount),
+                                "parent_descriptor": self.descriptor.clone(),
+                                "parent_descriptor_checksum": self.get_name()?,
+                                "derivation_index": derivation_index_of_script(wallet, &output.script_pubkey),
                                 "coin_type": coin_type,
                                 "timestamp": format!("{}", a.confirmation_time),
                                 "blockheight": format!("{}", a.confirmation_height),
                         }});
+                        attach_fiat_fields(&mut onchain_deposit[UTXO_DEPOSIT_TAG], amount, rate.as_ref());
                         log::trace!("INSIDE SEND DEPOSIT NOTIFICATION ON SMAUG SIDE");
+                        broadcast_event(event_tx, onchain_deposit.clone());
                         let cloned_plugin = plugin.clone();
                         tokio::spawn(async move {
                             if let Err(e) = cloned_plugin
@@ -556,10 +1468,16 @@ impl DescriptorWallet {
     async fn shared_tx_notify<'a>(
         &self,
         plugin: &Plugin<State>,
+        event_tx: &broadcast::Sender<serde_json::Value>,
         wallet: &Wallet<Store<'_, bdk::wallet::ChangeSet>>,
         tx: &CanonicalTx<'_, Transaction, ConfirmationTimeAnchor>,
+        fiat_oracle: &mut PriceOracle,
     ) -> Result<(), Error> {
         let coin_type = parse_currency(&self.network)?;
+        let rate = match tx.chain_position {
+            ChainPosition::Unconfirmed(_) => None,
+            ChainPosition::Confirmed(a) => fiat_oracle.rate_at(a.confirmation_height),
+        };
         for input in tx.tx_node.input.iter() {
             if let Some(po) = wallet.tx_graph().get_txout(input.previous_output) {
                 match tx.chain_position {
@@ -578,16 +1496,21 @@ impl DescriptorWallet {
                         let amount = po.value;
                         let outpoint = format!("{}", input.previous_output.to_string());
                         log::trace!("outpoint = {}", format!("{}", outpoint));
-                        let onchain_spend = json!({UTXO_SPENT_TAG: {
+                        let mut onchain_spend = json!({UTXO_SPENT_TAG: {
                             "account": acct,
                             "outpoint": outpoint,
                             "spending_txid": tx.tx_node.txid.to_string(),
                             "amount_msat": Self::sats_to_msats(amount),
+                            "parent_descriptor": self.descriptor.clone(),
+                            "parent_descriptor_checksum": self.get_name()?,
+                            "derivation_index": derivation_index_of_script(wallet, &po.script_pubkey),
                             "coin_type": coin_type,
                             "timestamp": format!("{}", a.confirmation_time),
                             "blockheight": format!("{}", a.confirmation_height),
                         }});
+                        attach_fiat_fields(&mut onchain_spend[UTXO_SPENT_TAG], amount, rate.as_ref());
                         log::trace!("INSIDE SEND SPEND NOTIFICATION ON SMAUG SIDE");
+                        broadcast_event(event_tx, onchain_spend.clone());
                         let cloned_plugin = plugin.clone();
                         tokio::spawn(async move {
                             if let Err(e) = cloned_plugin
@@ -628,17 +1551,22 @@ impl DescriptorWallet {
                     let amount = output.value;
                     let outpoint = format!("{}:{}", tx.tx_node.txid, vout);
                     log::trace!("outpoint = {}", format!("{}:{}", tx.tx_node.txid, vout));
-                    let onchain_deposit = json!({UTXO_DEPOSIT_TAG: {
+                    let mut onchain_deposit = json!({UTXO_DEPOSIT_TAG: {
                             "account": acct,
                             "transfer_from": transfer_from,
                             "outpoint": outpoint,
                             "spending_txid": tx.tx_node.txid,
                             "amount_msat": Self::sats_to_msats(amount),
+                            "parent_descriptor": self.descriptor.clone(),
+                            "parent_descriptor_checksum": self.get_name()?,
+                            "derivation_index": derivation_index_of_script(wallet, &output.script_pubkey),
                             "coin_type": coin_type,
                             "timestamp": format!("{}", a.confirmation_time),
                             "blockheight": format!("{}", a.confirmation_height),
                     }});
+                    attach_fiat_fields(&mut onchain_deposit[UTXO_DEPOSIT_TAG], amount, rate.as_ref());
                     log::trace!("INSIDE SEND DEPOSIT NOTIFICATION ON SMAUG SIDE");
+                    broadcast_event(event_tx, onchain_deposit.clone());
                     let cloned_plugin = plugin.clone();
                     tokio::spawn(async move {
                         if let Err(e) = cloned_plugin
@@ -654,13 +1582,106 @@ impl DescriptorWallet {
         Ok(())
     }
 
+    /// Diffs the wallet's current canonical txs against the set of
+    /// confirmations we've already notified for ([`notified_confirmations`])
+    /// and emits a compensating [`REORG_TAG`] notification for any tx that
+    /// was previously confirmed and has since been dropped or moved to a
+    /// different height. Call this once per sync, before processing new
+    /// txs, so the notification stream stays self-correcting across reorgs.
+    ///
+    /// [`notified_confirmations`]: DescriptorWallet::notified_confirmations
+    pub async fn notify_reorgs<'a>(
+        &mut self,
+        plugin: &Plugin<State>,
+        transactions: &[CanonicalTx<'a, Transaction, ConfirmationTimeAnchor>],
+    ) -> Result<(), Error> {
+        let coin_type = parse_currency(&self.network)?;
+        let mut current_confirmed: BTreeMap<Txid, u32> = BTreeMap::new();
+        for tx in transactions {
+            if let ChainPosition::Confirmed(a) = tx.chain_position {
+                current_confirmed.insert(tx.tx_node.txid, a.confirmation_height);
+            }
+        }
+
+        let reorged: Vec<(Txid, u32, Option<u32>)> = self
+            .notified_confirmations
+            .iter()
+            .filter_map(|(&txid, &old_height)| match current_confirmed.get(&txid) {
+                Some(&new_height) if new_height != old_height => {
+                    Some((txid, old_height, Some(new_height)))
+                }
+                None => Some((txid, old_height, None)),
+                _ => None,
+            })
+            .collect();
+
+        for (txid, old_height, new_height) in reorged {
+            match new_height {
+                Some(h) => {
+                    self.notified_confirmations.insert(txid, h);
+                }
+                None => {
+                    self.notified_confirmations.remove(&txid);
+                }
+            }
+            log::warn!(
+                "reorg detected for txid {}: was confirmed at height {}, now {:?}",
+                txid,
+                old_height,
+                new_height
+            );
+            let acct = format!("smaug:{}", self.get_name()?);
+            // Compensate every outpoint this tx created in our own UTXO set:
+            // those amounts were already counted by a prior utxo_deposit
+            // notification, so a bookkeeper needs the negated amount per
+            // outpoint to back them out, not just the bare txid.
+            let txid_prefix = format!("{}:", txid);
+            let affected_outpoints: Vec<(String, u64)> = self
+                .utxos
+                .iter()
+                .filter(|(outpoint, _)| outpoint.starts_with(&txid_prefix))
+                .map(|(outpoint, utxo)| (outpoint.clone(), utxo.value))
+                .collect();
+            let reorg_notice = json!({REORG_TAG: {
+                "account": acct,
+                "txid": txid,
+                "old_blockheight": old_height,
+                "new_blockheight": new_height,
+                "coin_type": coin_type,
+                "outpoints": affected_outpoints
+                    .iter()
+                    .map(|(outpoint, value)| json!({
+                        "outpoint": outpoint,
+                        "amount_msat": -(Self::sats_to_msats(*value) as i64),
+                    }))
+                    .collect::<Vec<_>>(),
+            }});
+            let cloned_plugin = plugin.clone();
+            tokio::spawn(async move {
+                if let Err(e) = cloned_plugin
+                    .send_custom_notification(REORG_TAG.to_string(), reorg_notice)
+                    .await
+                {
+                    log::error!("Error sending custom notification: {:?}", e);
+                }
+            });
+        }
+        Ok(())
+    }
+
     pub async fn send_notifications_for_tx<'a>(
-        &self,
+        &mut self,
         plugin: &Plugin<State>,
+        event_tx: &broadcast::Sender<serde_json::Value>,
         wallet: &Wallet<Store<'_, bdk::wallet::ChangeSet>>,
         tx: CanonicalTx<'_, Transaction, ConfirmationTimeAnchor>,
+        fiat_oracle: &mut PriceOracle,
     ) -> Result<(), Error> {
         log::debug!("sending notifs for txid/tx: {:?} {:?}", tx.tx_node.txid, tx);
+        if let ChainPosition::Confirmed(a) = tx.chain_position {
+            self.notified_confirmations
+                .insert(tx.tx_node.txid, a.confirmation_height);
+        }
         // we own all inputs
         if tx.clone().tx_node.tx.input.iter().all(|x| {
             match wallet.tx_graph().get_txout(x.previous_output) {
@@ -679,7 +1700,7 @@ impl DescriptorWallet {
             }
         }) {
             log::debug!("sending spend notif");
-            self.spend_tx_notify(plugin, wallet, &tx).await?;
+            self.spend_tx_notify(plugin, event_tx, wallet, &tx, fiat_oracle).await?;
         } else
         // we own no inputs
         if !tx.clone().tx_node.tx.input.iter().any(|x| {
@@ -699,12 +1720,12 @@ impl DescriptorWallet {
             }
         }) {
             log::debug!("sending deposit notif");
-            self.receive_tx_notify(plugin, wallet, &tx).await?;
+            self.receive_tx_notify(plugin, event_tx, wallet, &tx, fiat_oracle).await?;
         }
         // we own some inputs but not others
         else {
             log::debug!("sending shared notif");
-            self.shared_tx_notify(plugin, wallet, &tx).await?;
+            self.shared_tx_notify(plugin, event_tx, wallet, &tx, fiat_oracle).await?;
         }
         Ok(())
     }
@@ -721,7 +1742,7 @@ impl TryFrom<serde_json::Value> for DescriptorWallet {
                 let param_count = a.len();
 
                 match param_count {
-                    1..=4 => {
+                    1..=5 => {
                         let descriptor = a.get(0).unwrap().as_str().ok_or_else(|| WatchError::InvalidDescriptor("descriptor must be a string".to_string()))?;
                         log::trace!("try_from array: change_descriptor = {:?}", a.get(1));
                         let change_descriptor = if let Some(cd) = a.get(1) {
@@ -739,24 +1760,52 @@ impl TryFrom<serde_json::Value> for DescriptorWallet {
                         } else {
                             None
                         };
+                        let source = if let Some(s) = a.get(4) {
+                            Some(parse_chain_source(s.as_str().ok_or_else(|| WatchError::InvalidFormat(format!("source must be a string. Received: {s}")))?)?)
+                        } else {
+                            None
+                        };
 
-                        DescriptorWallet::new(descriptor, change_descriptor, birthday, gap, None)
+                        DescriptorWallet::new(descriptor, change_descriptor, birthday, gap, None, source)
                     }
-                    _ => Err(WatchError::InvalidFormat(format!("Unexpected request format. The request needs 1-4 parameters. Received: {param_count}"))),
+                    _ => Err(WatchError::InvalidFormat(format!("Unexpected request format. The request needs 1-5 parameters. Received: {param_count}"))),
                 }
             },
+            serde_json::Value::Object(m) if m.contains_key("blockheight") || m.contains_key("label") => {
+                // BDK's "fully-noded" wallet-export shape: a single
+                // `descriptor`, `blockheight` (birthday), and a `label` we
+                // just ignore. There's no separate change descriptor -- it's
+                // implied by swapping the final `/0/*` step for `/1/*`.
+                log::trace!("try_from: BDK wallet-export object detected");
+                let descriptor = m
+                    .get("descriptor")
+                    .and_then(|d| d.as_str())
+                    .ok_or_else(|| WatchError::InvalidDescriptor("descriptor is mandatory".to_string()))?;
+                let change_descriptor = derive_change_descriptor(descriptor);
+                let birthday = match m.get("blockheight") {
+                    Some(b) => Some(b.as_u64().ok_or_else(|| {
+                        WatchError::InvalidBirthday(format!("blockheight must be a number. Received: {b}"))
+                    })?),
+                    None => None,
+                };
+                DescriptorWallet::new(descriptor, change_descriptor.as_deref(), birthday, None, None, None)
+            }
             serde_json::Value::Object(m) => {
                 log::trace!("try_from: object detected");
-                let allowed_keys = ["descriptor", "change_descriptor", "birthday", "gap"];
+                let allowed_keys = ["descriptor", "change_descriptor", "birthday", "gap", "source"];
                 let param_count = m.len();
 
                  if m.is_empty() || param_count > allowed_keys.len() {
-                    Err(WatchError::InvalidFormat(format!("Unexpected request format. The request needs 1-4 parameters. Received: {param_count}")))
+                    Err(WatchError::InvalidFormat(format!("Unexpected request format. The request needs 1-5 parameters. Received: {param_count}")))
                  } else if !m.contains_key(allowed_keys[0]){
                     Err(WatchError::InvalidDescriptor(format!("{} is mandatory", allowed_keys[0])))
                  } else if !m.iter().all(|(k, _)| allowed_keys.contains(&k.as_str())) {
-                    Err(WatchError::InvalidFormat(format!("Invalid named parameter found in request. Allowed named params: ['descriptor', 'change_descriptor', 'birthday', 'gap']")))
+                    Err(WatchError::InvalidFormat(format!("Invalid named parameter found in request. Allowed named params: ['descriptor', 'change_descriptor', 'birthday', 'gap', 'source']")))
                  } else {
+                    let source = match m.get("source") {
+                        Some(s) => Some(parse_chain_source(s.as_str().ok_or_else(|| WatchError::InvalidFormat(format!("source must be a string. Received: {s}")))?)?),
+                        None => None,
+                    };
                     DescriptorWallet::new(
                         m.get("descriptor").unwrap().as_str().unwrap(),
                         match m.get("change_descriptor") {
@@ -772,6 +1821,7 @@ impl TryFrom<serde_json::Value> for DescriptorWallet {
                             None => None,
                         },
                         None,
+                        source,
                     )
                 }
             },